@@ -0,0 +1,55 @@
+// Old monitor look for the arena, drawn over everything the 2D camera sees. The strength of
+// each part comes from the crt settings, 0.0 turns it off.
+#import bevy_core_pipeline::fullscreen_vertex_shader FullscreenVertexOutput
+
+struct CrtSettings {
+    scanlines: f32,
+    curvature: f32,
+    bloom: f32,
+    aberration: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: CrtSettings;
+
+// Bends the picture outwards as if it were on the curved glass of the tube
+fn curve(uv: vec2<f32>) -> vec2<f32> {
+    let centred = uv * 2.0 - 1.0;
+    let bent = centred * (1.0 + settings.curvature * 0.2 * centred.yx * centred.yx);
+    return bent * 0.5 + 0.5;
+}
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let uv = curve(in.uv);
+    // Past the edge of the bent picture is the black of the monitor's case. Every pixel still
+    // samples the texture, sampling has to happen in uniform control flow.
+    let inside = f32(all(uv >= vec2(0.0)) && all(uv <= vec2(1.0)));
+
+    // Red and blue drift apart towards the edges of the screen
+    let shift = (uv - 0.5) * settings.aberration * 0.006;
+    var colour = vec3(
+        textureSample(screen_texture, texture_sampler, uv + shift).r,
+        textureSample(screen_texture, texture_sampler, uv).g,
+        textureSample(screen_texture, texture_sampler, uv - shift).b,
+    );
+
+    // The bright sprites glow over the pixels around them
+    let texel = 2.0 / vec2<f32>(textureDimensions(screen_texture));
+    var glow = vec3(0.0);
+    for (var x = -2; x <= 2; x++) {
+        for (var y = -2; y <= 2; y++) {
+            let offset = vec2(f32(x), f32(y)) * texel;
+            let sample = textureSample(screen_texture, texture_sampler, uv + offset).rgb;
+            glow += max(sample - 0.6, vec3(0.0));
+        }
+    }
+    colour += glow * settings.bloom * 0.15;
+
+    // Every third row of pixels is darker
+    let line = 0.5 + 0.5 * sin(in.position.y * 2.094);
+    colour *= 1.0 - settings.scanlines * 0.4 * line;
+
+    return vec4(colour * inside, 1.0);
+}
@@ -0,0 +1,140 @@
+use bevy::{
+    a11y::{
+        accesskit::{Live, NodeBuilder, Role},
+        AccessibilityNode,
+    },
+    prelude::*,
+};
+
+use crate::{
+    ball::Ball,
+    events::{GoalScored, MatchWon, ScoringSet},
+    four_player::{team, Lives},
+    menu::MenuText,
+    player::PlayerType,
+    profiles::PlayerNames,
+    score::Score,
+    settings::{GameMode, Settings},
+};
+
+// A live region screen readers read out whenever its text changes. Everything worth hearing
+// about in a match is written to it.
+#[derive(Component)]
+struct Announcer;
+
+// Reads out goals, the score, who's serving and the selected menu item through AccessKit,
+// for the windowed game
+pub struct AnnouncementsPlugin;
+
+impl Plugin for AnnouncementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_announcer).add_systems(
+            Update,
+            (
+                announce_goals.after(ScoringSet),
+                announce_server,
+                announce_menu_focus,
+            )
+                .chain(),
+        );
+    }
+}
+
+fn spawn_announcer(mut commands: Commands) {
+    let mut node = NodeBuilder::new(Role::Status);
+    node.set_live(Live::Polite);
+
+    commands.spawn((AccessibilityNode(node), Announcer));
+}
+
+fn announce(announcer_query: &mut Query<&mut AccessibilityNode, With<Announcer>>, text: String) {
+    for mut node in announcer_query.iter_mut() {
+        node.set_name(text.clone());
+    }
+}
+
+// Whoever wins the match, with their partner in the team modes
+fn winner_name(names: &PlayerNames, settings: &Settings, winner: PlayerType) -> String {
+    match settings.mode {
+        GameMode::Doubles | GameMode::Teams => {
+            let partner = match team(winner) {
+                PlayerType::Player1 => PlayerType::Player3,
+                _ => PlayerType::Player4,
+            };
+            format!("{} and {}", names.name(team(winner)), names.name(partner))
+        }
+        _ => names.name(winner).to_string(),
+    }
+}
+
+// The score after each goal, or the lives left in the four player modes
+fn announce_goals(
+    mut announcer_query: Query<&mut AccessibilityNode, With<Announcer>>,
+    mut goals: EventReader<GoalScored>,
+    mut match_won: EventReader<MatchWon>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    names: Res<PlayerNames>,
+    settings: Res<Settings>,
+) {
+    let mut sentences = Vec::new();
+
+    for goal in goals.iter() {
+        if settings.mode.is_four_player() {
+            let conceded = names.name(goal.conceded);
+            sentences.push(match lives.get(goal.conceded) {
+                0 => format!("{conceded} is out"),
+                1 => format!("{conceded} has 1 life left"),
+                left => format!("{conceded} has {left} lives left"),
+            });
+        } else {
+            sentences.push(format!(
+                "{} scores. {} {}, {} {}",
+                names.name(goal.scorer),
+                names.player1,
+                score.points(PlayerType::Player1),
+                names.player2,
+                score.points(PlayerType::Player2)
+            ));
+        }
+    }
+    for won in match_won.iter() {
+        sentences.push(format!(
+            "{} won the match",
+            winner_name(&names, &settings, won.winner)
+        ));
+    }
+
+    if !sentences.is_empty() {
+        announce(&mut announcer_query, sentences.join(". "));
+    }
+}
+
+// Each new ball is waiting on its server
+fn announce_server(
+    mut announcer_query: Query<&mut AccessibilityNode, With<Announcer>>,
+    ball_query: Query<&Ball, Added<Ball>>,
+    names: Res<PlayerNames>,
+) {
+    for ball in ball_query.iter() {
+        announce(
+            &mut announcer_query,
+            format!("{} to serve", names.name(ball.owner())),
+        );
+    }
+}
+
+fn announce_menu_focus(
+    mut announcer_query: Query<&mut AccessibilityNode, With<Announcer>>,
+    menu_text: Res<MenuText>,
+    mut last_focused: Local<String>,
+) {
+    if menu_text.focused == *last_focused {
+        return;
+    }
+    *last_focused = menu_text.focused.clone();
+
+    if !menu_text.focused.is_empty() {
+        announce(&mut announcer_query, menu_text.focused.clone());
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    files::read_asset_ron,
+    player::{Lane, PlayerType},
+};
+
+// In the assets folder
+const ARENAS_PATH: &str = "arenas.ron";
+
+// Room around the arena that must also be on screen, for the scoreboard and the goals
+const VIEW_MARGIN: f32 = 100.0;
+// How far each goal reaches past the ends of its side, in goal widths. Long enough that
+// the goals overlap at the corners and no ball can get out between them.
+const GOAL_OVERLAP: f32 = 3.0;
+
+// Dimensions of the arena being played in. Every system reads sizes from here rather
+// than from constants, so a different arena can be picked for each match.
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct ArenaConfig {
+    pub width: f32,
+    pub height: f32,
+    pub goal_width: f32,
+    pub paddle_width: f32,
+    pub paddle_height: f32,
+    // Distance of each paddle from the centre line
+    pub paddle_offset: f32,
+}
+
+impl Default for ArenaConfig {
+    fn default() -> Self {
+        ArenaConfig {
+            width: 800.0,
+            height: 600.0,
+            goal_width: 100.0,
+            paddle_width: 10.0,
+            paddle_height: 60.0,
+            paddle_offset: 300.0,
+        }
+    }
+}
+
+impl ArenaConfig {
+    // Checks the arena can be played in. Every paddle has to start inside it, and the classic
+    // paddle, which is played when no character fits, has to fit along every side's track.
+    fn check(&self) -> Result<(), String> {
+        for (field, value) in [
+            ("width", self.width),
+            ("height", self.height),
+            ("goal_width", self.goal_width),
+            ("paddle_width", self.paddle_width),
+            ("paddle_height", self.paddle_height),
+            ("paddle_offset", self.paddle_offset),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("{field} must be more than 0, got {value}"));
+            }
+        }
+
+        if self.paddle_offset >= self.width / 2.0 {
+            return Err(format!(
+                "paddle_offset {} puts the side paddles outside the arena",
+                self.paddle_offset
+            ));
+        }
+        if self.width / 2.0 - self.paddle_offset >= self.height / 2.0 {
+            return Err(format!(
+                "paddle_offset {} puts the top and bottom paddles outside the arena",
+                self.paddle_offset
+            ));
+        }
+        if PlayerType::ALL
+            .into_iter()
+            .any(|player| self.track_limit(player, self.paddle_height) < 0.0)
+        {
+            return Err(format!(
+                "paddle_height {} makes the paddles too long for the arena",
+                self.paddle_height
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn view_size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height) + Vec2::splat(VIEW_MARGIN * 2.0)
+    }
+
+    // Size of a paddle guarding the given player's goal, `length` is along its track
+    pub fn paddle_size(&self, player: PlayerType, length: f32) -> Vec2 {
+        if player.is_horizontal() {
+            Vec2::new(length, self.paddle_width)
+        } else {
+            Vec2::new(self.paddle_width, length)
+        }
+    }
+
+    // Where each paddle starts, and returns to between rounds. The top and bottom paddles
+    // are as far in from their edge as the side paddles are from theirs, forward paddles
+    // play halfway between the back paddle and the centre.
+    pub fn paddle_position(&self, team: PlayerType, lane: Lane) -> Vec3 {
+        let inset = self.width / 2.0 - self.paddle_offset;
+
+        let back = match team {
+            PlayerType::Player1 => Vec3::new(-self.paddle_offset, 0.0, 0.0),
+            PlayerType::Player2 => Vec3::new(self.paddle_offset, 0.0, 0.0),
+            PlayerType::Player3 => Vec3::new(0.0, self.height / 2.0 - inset, 0.0),
+            PlayerType::Player4 => Vec3::new(0.0, -self.height / 2.0 + inset, 0.0),
+        };
+
+        match lane {
+            Lane::Back => back,
+            Lane::Forward => back / 2.0,
+        }
+    }
+
+    // How far each paddle can move from the middle of its track. The top and bottom
+    // paddles stay between the side paddles.
+    pub fn track_limit(&self, player: PlayerType, length: f32) -> f32 {
+        if player.is_horizontal() {
+            self.paddle_offset - self.paddle_width - length / 2.0
+        } else {
+            self.height / 2.0 - length / 2.0
+        }
+    }
+
+    pub fn goal_size(&self, player: PlayerType) -> Vec2 {
+        let overlap = self.goal_width * GOAL_OVERLAP * 2.0;
+
+        if player.is_horizontal() {
+            Vec2::new(self.width + overlap, self.goal_width)
+        } else {
+            Vec2::new(self.goal_width, self.height + overlap)
+        }
+    }
+
+    pub fn goal_position(&self, player: PlayerType) -> Vec3 {
+        let distance = if player.is_horizontal() {
+            self.height / 2.0 + self.goal_width * 2.0
+        } else {
+            self.width / 2.0 + self.goal_width * 2.0
+        };
+
+        -player.inward() * distance
+    }
+
+    // Where the counter for each side is shown, in the margin just outside it
+    pub fn label_position(&self, player: PlayerType) -> Vec3 {
+        let half_extent = if player.is_horizontal() {
+            self.height / 2.0
+        } else {
+            self.width / 2.0
+        };
+
+        -player.inward() * (half_extent + VIEW_MARGIN / 2.0) + Vec3::Z
+    }
+
+    // Height of the score counters above the centre of the arena
+    pub fn scoreboard_y(&self) -> f32 {
+        self.height / 2.0 + VIEW_MARGIN / 2.0
+    }
+}
+
+#[derive(Deserialize)]
+struct ArenaFile {
+    default: String,
+    arenas: HashMap<String, ArenaConfig>,
+}
+
+// Which sides of the arena the ball bounces off. The top and bottom are walls in the
+// classic mode, in the four player modes a side becomes a wall once its player is out.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Walls {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+impl Default for Walls {
+    fn default() -> Self {
+        Walls {
+            left: false,
+            right: false,
+            top: true,
+            bottom: true,
+        }
+    }
+}
+
+impl Walls {
+    // Whether the side guarded by the given player is a wall
+    pub fn is_wall(&self, player: PlayerType) -> bool {
+        match player {
+            PlayerType::Player1 => self.left,
+            PlayerType::Player2 => self.right,
+            PlayerType::Player3 => self.top,
+            PlayerType::Player4 => self.bottom,
+        }
+    }
+
+    pub fn set_wall(&mut self, player: PlayerType, wall: bool) {
+        match player {
+            PlayerType::Player1 => self.left = wall,
+            PlayerType::Player2 => self.right = wall,
+            PlayerType::Player3 => self.top = wall,
+            PlayerType::Player4 => self.bottom = wall,
+        }
+    }
+}
+
+// Checks an arena chosen in the settings is in the arenas file, the error lists the ones that
+// are. Any name is let through when the file can't be read, `ArenaPlugin` warns about that
+// and plays the classic arena.
+pub fn check_arena_name(name: &str) -> Result<(), String> {
+    let Ok(file) = read_asset_ron::<ArenaFile>(ARENAS_PATH) else {
+        return Ok(());
+    };
+    if let Some(arena) = file.arenas.get(name) {
+        return arena.check().map_err(|reason| {
+            format!("arena {name:?} in {ARENAS_PATH} can't be played, {reason}")
+        });
+    }
+
+    let mut names: Vec<_> = file.arenas.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    Err(format!(
+        "no arena named {name:?} in {ARENAS_PATH}, choose from {}",
+        names.join(", ")
+    ))
+}
+
+#[derive(Default)]
+pub struct ArenaPlugin {
+    // Name of the arena to play in, the file's default is used when this is None
+    pub arena: Option<String>,
+}
+
+impl Plugin for ArenaPlugin {
+    fn build(&self, app: &mut App) {
+        let file = read_asset_ron::<ArenaFile>(ARENAS_PATH)
+            .map_err(|err| warn!("{err}, using the classic arena"))
+            .ok();
+
+        let (default, arenas) = match file {
+            Some(file) => (file.default, file.arenas),
+            None => (String::new(), HashMap::new()),
+        };
+        let arenas: HashMap<String, ArenaConfig> = arenas
+            .into_iter()
+            .filter(|(name, arena)| match arena.check() {
+                Ok(()) => true,
+                Err(reason) => {
+                    warn!("Skipping {name:?} in {ARENAS_PATH}: {reason}");
+                    false
+                }
+            })
+            .collect();
+
+        let name = self.arena.as_ref().unwrap_or(&default);
+        let arena = arenas.get(name).cloned().unwrap_or_else(|| {
+            if !arenas.is_empty() {
+                warn!("No arena named {name:?} in {ARENAS_PATH}, using the classic arena");
+            }
+            ArenaConfig::default()
+        });
+
+        app.insert_resource(arena).insert_resource(Walls::default());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_arena_in_the_file_can_be_played() {
+        let file = read_asset_ron::<ArenaFile>(ARENAS_PATH).unwrap();
+
+        assert!(ArenaConfig::default().check().is_ok());
+        for (name, arena) in &file.arenas {
+            assert!(arena.check().is_ok(), "{name} can't be played");
+        }
+        assert!(check_arena_name(&file.default).is_ok());
+    }
+
+    #[test]
+    fn paddles_must_fit_inside_the_arena() {
+        let too_long = ArenaConfig {
+            paddle_height: 650.0,
+            ..default()
+        };
+        let outside = ArenaConfig {
+            paddle_offset: 450.0,
+            ..default()
+        };
+        let flat = ArenaConfig {
+            height: 100.0,
+            ..default()
+        };
+
+        assert!(too_long.check().unwrap_err().contains("paddle_height"));
+        assert!(outside.check().unwrap_err().contains("side paddles"));
+        assert!(flat.check().unwrap_err().contains("top and bottom"));
+    }
+}
@@ -1,15 +1,108 @@
 use bevy::prelude::*;
 
-use crate::TILE_SIZE;
+use crate::{
+    arena::ArenaConfig,
+    ascii_text::{AsciiText, AsciiTextBundle},
+    four_player::Lives,
+    menu::MenuText,
+    player::PlayerType,
+    round::{Countdown, RoundPhase},
+    score::Score,
+    settings::Settings,
+    stats::MatchStats,
+    TILE_SIZE,
+};
+
+// CP437 glyph indices in Ascii.png
+pub const GLYPH_BALL: usize = 254;
+pub const GLYPH_SOLID: usize = 219;
+pub const GLYPH_VERTICAL_LINE: usize = 179;
+pub const GLYPH_DOUBLE_HORIZONTAL_LINE: usize = 205;
+
+const SCORE_GLYPH_SIZE: f32 = 30.0;
+const COUNTDOWN_GLYPH_SIZE: f32 = 60.0;
+const SUMMARY_GLYPH_SIZE: f32 = 14.0;
 
 pub struct AsciiPlugin;
 
 #[derive(Resource)]
 pub struct AsciiSheet(pub Handle<TextureAtlas>);
 
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DisplayMode {
+    #[default]
+    Standard,
+    Ascii,
+}
+
+// Drawn as tiles of the given glyph, covering the entity's sprite, when in ASCII mode
+#[derive(Component)]
+pub struct AsciiArt {
+    pub glyph: usize,
+}
+
+#[derive(Component)]
+struct AsciiGlyph;
+
+// Systems that resize or recolour sprites. Glyphs are laid out again after them, so they
+// always match the sprite they cover.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct SpriteStyleSet;
+
+// The sprite size and colour an entity's glyphs were laid out for
+#[derive(Component, Clone, Copy, PartialEq)]
+struct GlyphLayout {
+    size: Vec2,
+    color: Color,
+}
+
+// Entities that are only visible in the standard display mode
+#[derive(Component)]
+pub struct StandardOnly;
+
+// Entities that are only visible in the ASCII display mode
+#[derive(Component)]
+pub struct AsciiOnly;
+
+#[derive(Component)]
+struct AsciiScore(PlayerType);
+
+#[derive(Component)]
+struct AsciiCountdown;
+
+#[derive(Component)]
+struct AsciiSummary;
+
+#[derive(Component)]
+struct AsciiMenu;
+
 impl Plugin for AsciiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreStartup, load_ascii);
+        app.add_state::<DisplayMode>()
+            .add_systems(PreStartup, load_ascii)
+            .add_systems(
+                Startup,
+                (
+                    spawn_ascii_scores,
+                    spawn_ascii_countdown,
+                    spawn_ascii_summary,
+                    spawn_ascii_menu,
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    toggle_display_mode,
+                    attach_ascii_art.after(SpriteStyleSet),
+                    update_ascii_scores,
+                    update_ascii_countdown,
+                    update_ascii_menu,
+                ),
+            )
+            .add_systems(OnEnter(RoundPhase::MatchOver), show_ascii_summary)
+            .add_systems(OnExit(RoundPhase::MatchOver), hide_ascii_summary)
+            .add_systems(OnEnter(DisplayMode::Standard), apply_display_mode)
+            .add_systems(OnEnter(DisplayMode::Ascii), apply_display_mode);
     }
 }
 
@@ -25,17 +118,17 @@ pub fn spawn_ascii_sprite(
     sprite.color = color;
     sprite.custom_size = Some(Vec2::splat(TILE_SIZE));
 
-    return commands
+    commands
         .spawn(SpriteSheetBundle {
-            sprite: sprite,
+            sprite,
             texture_atlas: ascii.0.clone(),
             transform: Transform {
-                translation: translation,
+                translation,
                 ..default()
             },
             ..default()
         })
-        .id();
+        .id()
 }
 
 fn load_ascii(
@@ -57,3 +150,235 @@ fn load_ascii(
 
     commands.insert_resource(AsciiSheet(atlas_handle))
 }
+
+fn toggle_display_mode(
+    keyboard: Res<Input<KeyCode>>,
+    mode: Res<State<DisplayMode>>,
+    mut next_mode: ResMut<NextState<DisplayMode>>,
+) {
+    if keyboard.just_pressed(KeyCode::F2) {
+        next_mode.set(match mode.get() {
+            DisplayMode::Standard => DisplayMode::Ascii,
+            DisplayMode::Ascii => DisplayMode::Standard,
+        });
+    }
+}
+
+// Covers each sprite with glyphs, and lays them out again when the sprite is resized or
+// recoloured, e.g. by a character's paddle size or the theme
+#[allow(clippy::type_complexity)]
+fn attach_ascii_art(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    mode: Res<State<DisplayMode>>,
+    mut art_query: Query<
+        (
+            Entity,
+            &AsciiArt,
+            &mut Sprite,
+            Option<&GlyphLayout>,
+            Option<&Children>,
+        ),
+        Or<(Added<AsciiArt>, Changed<Sprite>)>,
+    >,
+    glyph_query: Query<(), With<AsciiGlyph>>,
+) {
+    for (entity, art, mut sprite, layout, children) in art_query.iter_mut() {
+        // The sprite's alpha only says whether it's hidden behind its glyphs
+        let new_layout = GlyphLayout {
+            size: sprite.custom_size.unwrap_or(Vec2::splat(TILE_SIZE)),
+            color: sprite.color.with_a(1.0),
+        };
+        if layout == Some(&new_layout) {
+            continue;
+        }
+
+        for child in children.into_iter().flatten() {
+            if glyph_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+
+        let GlyphLayout { size, color } = new_layout;
+        let columns = (size.x / TILE_SIZE).ceil().max(1.0) as usize;
+        let rows = (size.y / TILE_SIZE).ceil().max(1.0) as usize;
+
+        for column in 0..columns {
+            for row in 0..rows {
+                let translation = Vec3::new(
+                    (column as f32 - (columns - 1) as f32 / 2.0) * TILE_SIZE,
+                    (row as f32 - (rows - 1) as f32 / 2.0) * TILE_SIZE,
+                    0.1,
+                );
+                let glyph =
+                    spawn_ascii_sprite(&mut commands, &ascii, art.glyph, color, translation);
+
+                commands
+                    .entity(glyph)
+                    .insert((AsciiGlyph, visibility_for(*mode.get(), DisplayMode::Ascii)));
+                commands.entity(entity).add_child(glyph);
+            }
+        }
+        commands.entity(entity).insert(new_layout);
+
+        if *mode.get() == DisplayMode::Ascii {
+            sprite.color.set_a(0.0);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn apply_display_mode(
+    mode: Res<State<DisplayMode>>,
+    mut sprite_query: Query<&mut Sprite, With<AsciiArt>>,
+    mut glyph_query: Query<&mut Visibility, With<AsciiGlyph>>,
+    mut standard_query: Query<&mut Visibility, (With<StandardOnly>, Without<AsciiGlyph>)>,
+    mut ascii_query: Query<
+        &mut Visibility,
+        (With<AsciiOnly>, Without<StandardOnly>, Without<AsciiGlyph>),
+    >,
+) {
+    let mode = *mode.get();
+
+    // The sprite is hidden by making it transparent, hiding the entity would hide its glyphs too
+    for mut sprite in sprite_query.iter_mut() {
+        sprite
+            .color
+            .set_a(if mode == DisplayMode::Ascii { 0.0 } else { 1.0 });
+    }
+    for mut visibility in glyph_query.iter_mut() {
+        *visibility = visibility_for(mode, DisplayMode::Ascii);
+    }
+    for mut visibility in standard_query.iter_mut() {
+        *visibility = visibility_for(mode, DisplayMode::Standard);
+    }
+    for mut visibility in ascii_query.iter_mut() {
+        *visibility = visibility_for(mode, DisplayMode::Ascii);
+    }
+}
+
+fn visibility_for(mode: DisplayMode, visible_in: DisplayMode) -> Visibility {
+    if mode == visible_in {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+// The four player modes show each player's lives on their side instead of the scores
+fn spawn_ascii_scores(mut commands: Commands, arena: Res<ArenaConfig>, settings: Res<Settings>) {
+    let positions: Vec<(PlayerType, Vec3)> = if settings.mode.is_four_player() {
+        PlayerType::ALL
+            .into_iter()
+            .map(|player| (player, arena.label_position(player)))
+            .collect()
+    } else {
+        vec![
+            (
+                PlayerType::Player1,
+                Vec3::new(-40.0, arena.scoreboard_y(), 1.0),
+            ),
+            (
+                PlayerType::Player2,
+                Vec3::new(40.0, arena.scoreboard_y(), 1.0),
+            ),
+        ]
+    };
+
+    for (player, position) in positions {
+        let mut text = AsciiTextBundle::new(AsciiText::new("00", SCORE_GLYPH_SIZE), position);
+        text.spatial.visibility = Visibility::Hidden;
+
+        commands.spawn((text, AsciiScore(player), AsciiOnly));
+    }
+}
+
+fn update_ascii_scores(
+    score: Res<Score>,
+    lives: Res<Lives>,
+    settings: Res<Settings>,
+    mut text_query: Query<(&AsciiScore, &mut AsciiText)>,
+) {
+    if !score.is_changed() && !lives.is_changed() {
+        return;
+    }
+
+    for (ascii_score, mut text) in text_query.iter_mut() {
+        text.text = if settings.mode.is_four_player() {
+            lives.label(ascii_score.0)
+        } else {
+            format!("{:02}", score.points(ascii_score.0))
+        };
+    }
+}
+
+fn spawn_ascii_countdown(mut commands: Commands) {
+    let mut text = AsciiTextBundle::new(
+        AsciiText::new("", COUNTDOWN_GLYPH_SIZE),
+        Vec3::new(0.0, 0.0, 2.0),
+    );
+    text.spatial.visibility = Visibility::Hidden;
+
+    commands.spawn((text, AsciiCountdown, AsciiOnly));
+}
+
+fn update_ascii_countdown(
+    countdown: Res<Countdown>,
+    mut text_query: Query<&mut AsciiText, With<AsciiCountdown>>,
+) {
+    if !countdown.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.text = countdown.label();
+    }
+}
+
+fn spawn_ascii_summary(mut commands: Commands) {
+    let mut text = AsciiTextBundle::new(
+        AsciiText::new("", SUMMARY_GLYPH_SIZE),
+        Vec3::new(0.0, 0.0, 2.0),
+    );
+    text.spatial.visibility = Visibility::Hidden;
+
+    commands.spawn((text, AsciiSummary, AsciiOnly));
+}
+
+fn show_ascii_summary(
+    stats: Res<MatchStats>,
+    mut text_query: Query<&mut AsciiText, With<AsciiSummary>>,
+) {
+    for mut text in text_query.iter_mut() {
+        text.text = stats.summary();
+    }
+}
+
+fn hide_ascii_summary(mut text_query: Query<&mut AsciiText, With<AsciiSummary>>) {
+    for mut text in text_query.iter_mut() {
+        text.text.clear();
+    }
+}
+
+fn spawn_ascii_menu(mut commands: Commands) {
+    let mut text = AsciiTextBundle::new(
+        AsciiText::new("", SUMMARY_GLYPH_SIZE),
+        Vec3::new(0.0, 0.0, 2.0),
+    );
+    text.spatial.visibility = Visibility::Hidden;
+
+    commands.spawn((text, AsciiMenu, AsciiOnly));
+}
+
+fn update_ascii_menu(
+    menu_text: Res<MenuText>,
+    mut text_query: Query<&mut AsciiText, With<AsciiMenu>>,
+) {
+    if !menu_text.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.text = menu_text.text.clone();
+    }
+}
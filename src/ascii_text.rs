@@ -0,0 +1,211 @@
+use bevy::prelude::*;
+
+use crate::ascii::AsciiSheet;
+
+// Shown for characters that have no CP437 glyph
+const UNKNOWN_GLYPH: usize = b'?' as usize;
+
+pub struct AsciiTextPlugin;
+
+impl Plugin for AsciiTextPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (layout_ascii_text, animate_ascii_text).chain());
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum AsciiTextAlignment {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum AsciiTextEffect {
+    #[default]
+    None,
+    // Every glyph is shown and hidden, once per period in seconds
+    Blink {
+        period: f32,
+    },
+    // Glyphs bob up and down, each a little behind the one before it
+    Wave {
+        amplitude: f32,
+        speed: f32,
+    },
+    // Glyphs cycle through the hues in turn, like a marquee
+    Rainbow {
+        speed: f32,
+    },
+}
+
+// A string drawn as glyph sprites from the ASCII sheet, centred on the entity's transform
+#[derive(Component, Clone, Debug)]
+pub struct AsciiText {
+    pub text: String,
+    pub color: Color,
+    pub glyph_size: f32,
+    pub alignment: AsciiTextAlignment,
+    pub effect: AsciiTextEffect,
+}
+
+impl AsciiText {
+    pub fn new(text: impl Into<String>, glyph_size: f32) -> Self {
+        AsciiText {
+            text: text.into(),
+            color: Color::WHITE,
+            glyph_size,
+            alignment: AsciiTextAlignment::default(),
+            effect: AsciiTextEffect::default(),
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_alignment(mut self, alignment: AsciiTextAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    pub fn with_effect(mut self, effect: AsciiTextEffect) -> Self {
+        self.effect = effect;
+        self
+    }
+}
+
+#[derive(Bundle)]
+pub struct AsciiTextBundle {
+    pub text: AsciiText,
+    pub spatial: SpatialBundle,
+}
+
+impl AsciiTextBundle {
+    pub fn new(text: AsciiText, translation: Vec3) -> Self {
+        AsciiTextBundle {
+            text,
+            spatial: SpatialBundle::from_transform(Transform::from_translation(translation)),
+        }
+    }
+}
+
+#[derive(Component)]
+struct AsciiTextGlyph {
+    // Position of the character within the text, used to offset effects
+    index: usize,
+    home: Vec3,
+}
+
+pub fn glyph_index(character: char) -> usize {
+    match character {
+        ' '..='~' => character as usize,
+        '█' => 219,
+        '▀' => 223,
+        '▄' => 220,
+        '│' => 179,
+        '─' => 196,
+        '═' => 205,
+        '║' => 186,
+        '■' => 254,
+        '•' => 7,
+        '♥' => 3,
+        '►' => 16,
+        '◄' => 17,
+        _ => UNKNOWN_GLYPH,
+    }
+}
+
+fn layout_ascii_text(
+    mut commands: Commands,
+    ascii: Res<AsciiSheet>,
+    text_query: Query<(Entity, &AsciiText), Changed<AsciiText>>,
+) {
+    for (entity, text) in text_query.iter() {
+        commands.entity(entity).despawn_descendants();
+
+        let lines: Vec<&str> = text.text.lines().collect();
+        let mut index = 0;
+
+        for (line_number, line) in lines.iter().enumerate() {
+            let width = line.chars().count() as f32 * text.glyph_size;
+            let start_x = match text.alignment {
+                AsciiTextAlignment::Left => 0.0,
+                AsciiTextAlignment::Center => -width / 2.0,
+                AsciiTextAlignment::Right => -width,
+            } + text.glyph_size / 2.0;
+            let y = ((lines.len() - 1) as f32 / 2.0 - line_number as f32) * text.glyph_size;
+
+            for (column, character) in line.chars().enumerate() {
+                if character == ' ' {
+                    index += 1;
+                    continue;
+                }
+
+                let home = Vec3::new(start_x + column as f32 * text.glyph_size, y, 0.0);
+                let mut sprite = TextureAtlasSprite::new(glyph_index(character));
+                sprite.color = text.color;
+                sprite.custom_size = Some(Vec2::splat(text.glyph_size));
+
+                let glyph = commands
+                    .spawn((
+                        SpriteSheetBundle {
+                            sprite,
+                            texture_atlas: ascii.0.clone(),
+                            transform: Transform::from_translation(home),
+                            ..default()
+                        },
+                        AsciiTextGlyph { index, home },
+                    ))
+                    .id();
+                commands.entity(entity).add_child(glyph);
+
+                index += 1;
+            }
+        }
+    }
+}
+
+fn animate_ascii_text(
+    time: Res<Time>,
+    text_query: Query<(&AsciiText, &Children)>,
+    mut glyph_query: Query<(
+        &AsciiTextGlyph,
+        &mut Transform,
+        &mut Visibility,
+        &mut TextureAtlasSprite,
+    )>,
+) {
+    let elapsed = time.elapsed_seconds();
+
+    for (text, children) in text_query.iter() {
+        for child in children.iter() {
+            let Ok((glyph, mut transform, mut visibility, mut sprite)) =
+                glyph_query.get_mut(*child)
+            else {
+                continue;
+            };
+
+            match text.effect {
+                AsciiTextEffect::None => {}
+                AsciiTextEffect::Blink { period } => {
+                    *visibility = if (elapsed / period).fract() < 0.5 {
+                        Visibility::Inherited
+                    } else {
+                        Visibility::Hidden
+                    };
+                }
+                AsciiTextEffect::Wave { amplitude, speed } => {
+                    let phase = elapsed * speed - glyph.index as f32 * 0.5;
+                    transform.translation = glyph.home + Vec3::Y * phase.sin() * amplitude;
+                }
+                AsciiTextEffect::Rainbow { speed } => {
+                    let hue = (elapsed * speed * 60.0 + glyph.index as f32 * 30.0) % 360.0;
+                    sprite.color = Color::hsla(hue, 1.0, 0.6, text.color.a());
+                }
+            }
+        }
+    }
+}
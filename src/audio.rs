@@ -1,29 +1,227 @@
 use bevy::prelude::*;
-use bevy_kira_audio::prelude::*;
+use bevy_kira_audio::{prelude::*, AudioSource};
 
-use crate::{ARENA_HEIGHT, ARENA_WIDTH};
+use serde::Deserialize;
+
+use crate::{
+    arena::ArenaConfig,
+    ball::{Ball, INITIAL_BALL_SPEED},
+    events::{GoalScored, PaddleHit, WallBounce},
+    files::read_asset_ron,
+    player::{Paddle, PlayerType},
+    settings::Settings,
+    synth::{render, Envelope, SynthSound, Waveform},
+};
+
+// In the assets folder
+const SFX_PATH: &str = "audio/sfx.ron";
+
+const MIN_PLAYBACK_RATE: f64 = 0.8;
+const MAX_PLAYBACK_RATE: f64 = 1.6;
+// How much faster than the serve speed the ball has to go to raise the pitch by one octave
+const OCTAVE_SPEED: f32 = 1000.0;
+
+// The audio cue's tone, a whole number of cycles long so it loops without a click
+const CUE_FREQUENCY: f32 = 440.0;
+const CUE_VOLUME: f64 = 0.4;
 
 pub struct GameAudioPlugin;
 
 impl Plugin for GameAudioPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((AudioPlugin))
-            .add_systems(Startup, start_background_audio);
+        app.add_plugins(AudioPlugin)
+            .add_systems(PreStartup, load_sfx)
+            .add_systems(Startup, start_audio_cue)
+            .add_systems(Update, (play_gameplay_sfx, update_audio_cue));
+    }
+}
+
+// A tone that plays while the ball is in play when audio cues are on
+#[derive(Resource)]
+struct AudioCue {
+    instance: Handle<AudioInstance>,
+}
+
+// Each effect is either an audio file from the assets folder or a sound generated by the synth
+#[derive(Deserialize, Clone, Debug)]
+pub enum SfxSource {
+    Sample(String),
+    Synth(SynthSound),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SfxDefinitions {
+    pub bounce: SfxSource,
+    pub hit: SfxSource,
+    pub win: SfxSource,
+}
+
+impl Default for SfxDefinitions {
+    fn default() -> Self {
+        SfxDefinitions {
+            bounce: SfxSource::Sample("audio/bounce.ogg".into()),
+            hit: SfxSource::Sample("audio/paddle_hit.ogg".into()),
+            win: SfxSource::Sample("audio/win.ogg".into()),
+        }
     }
 }
 
-fn start_background_audio(asset_server: Res<AssetServer>, audio: Res<Audio>) {
+#[derive(Resource)]
+pub struct SfxLibrary {
+    bounce: Handle<AudioSource>,
+    hit: Handle<AudioSource>,
+    win: Handle<AudioSource>,
+}
+
+fn load_sfx(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+) {
+    let definitions = read_asset_ron::<SfxDefinitions>(SFX_PATH).unwrap_or_else(|err| {
+        warn!("{err}, falling back to samples");
+        SfxDefinitions::default()
+    });
+
+    let mut load = |source: &SfxSource| match source {
+        SfxSource::Sample(path) => asset_server.load(path.as_str()),
+        SfxSource::Synth(sound) => audio_sources.add(AudioSource {
+            sound: render(sound),
+        }),
+    };
+
+    commands.insert_resource(SfxLibrary {
+        bounce: load(&definitions.bounce),
+        hit: load(&definitions.hit),
+        win: load(&definitions.win),
+    });
+}
+
+pub fn play_bounce(
+    sfx: &Res<SfxLibrary>,
+    audio: &Res<Audio>,
+    arena: &ArenaConfig,
+    position: Vec3,
+    speed: f32,
+) {
     audio
-        .play(asset_server.load("audio/background.ogg"))
-        .looped();
+        .play(sfx.bounce.clone())
+        .with_panning(panning_for(arena, position))
+        .with_playback_rate(playback_rate_for(speed));
 }
-pub fn play_bounce(asset_server: &Res<AssetServer>, audio: &Res<Audio>) {
-    audio.play(asset_server.load("audio/bounce.ogg"));
+pub fn play_win(sfx: &Res<SfxLibrary>, audio: &Res<Audio>) {
+    audio.play(sfx.win.clone());
 }
-pub fn play_win(asset_server: &Res<AssetServer>, audio: &Res<Audio>) {
-    audio.play(asset_server.load("audio/win.ogg"));
+
+pub fn play_hit(
+    sfx: &Res<SfxLibrary>,
+    audio: &Res<Audio>,
+    arena: &ArenaConfig,
+    position: Vec3,
+    speed: f32,
+) {
+    audio
+        .play(sfx.hit.clone())
+        .with_panning(panning_for(arena, position))
+        .with_playback_rate(playback_rate_for(speed));
+}
+
+fn play_gameplay_sfx(
+    mut paddle_hits: EventReader<PaddleHit>,
+    mut wall_bounces: EventReader<WallBounce>,
+    mut goals: EventReader<GoalScored>,
+    sfx: Res<SfxLibrary>,
+    audio: Res<Audio>,
+    arena: Res<ArenaConfig>,
+) {
+    for hit in paddle_hits.iter() {
+        play_bounce(&sfx, &audio, &arena, hit.position, hit.speed);
+    }
+    for bounce in wall_bounces.iter() {
+        play_hit(&sfx, &audio, &arena, bounce.position, bounce.speed);
+    }
+    for _ in goals.iter() {
+        play_win(&sfx, &audio);
+    }
 }
 
-pub fn play_hit(asset_server: &Res<AssetServer>, audio: &Res<Audio>) {
-    audio.play(asset_server.load("audio/paddle_hit.ogg"));
+// The tone starts silent and keeps looping, `update_audio_cue` turns it up while there's a
+// ball in play
+fn start_audio_cue(
+    mut commands: Commands,
+    mut audio_sources: ResMut<Assets<AudioSource>>,
+    audio: Res<Audio>,
+    settings: Res<Settings>,
+) {
+    if !settings.audio_cues {
+        return;
+    }
+
+    let tone = audio_sources.add(AudioSource {
+        sound: render(&SynthSound {
+            waveform: Waveform::Triangle,
+            frequency: CUE_FREQUENCY,
+            frequency_end: None,
+            duty: 0.5,
+            duration: 1.0,
+            envelope: Envelope {
+                attack: 0.0,
+                decay: 0.0,
+                sustain: 1.0,
+                release: 0.0,
+            },
+            volume: 0.5,
+        }),
+    });
+    let instance = audio.play(tone).looped().with_volume(0.0).handle();
+
+    commands.insert_resource(AudioCue { instance });
+}
+
+// For players who can't see the ball, the tone's pitch follows it. It's an octave higher
+// when the ball is at the top of the arena above player 1's paddle, an octave lower at the
+// bottom, and pans left and right with it.
+fn update_audio_cue(
+    cue: Option<Res<AudioCue>>,
+    mut audio_instances: ResMut<Assets<AudioInstance>>,
+    ball_query: Query<(&Ball, &Transform)>,
+    paddle_query: Query<(&Paddle, &Transform)>,
+    arena: Res<ArenaConfig>,
+) {
+    let Some(instance) = cue.and_then(|cue| audio_instances.get_mut(&cue.instance)) else {
+        return;
+    };
+
+    let paddle = paddle_query
+        .iter()
+        .find(|(paddle, _)| paddle.player_type == PlayerType::Player1);
+    let (Some((paddle, paddle_transform)), Ok((ball, ball_transform))) =
+        (paddle, ball_query.get_single())
+    else {
+        instance.set_volume(Volume::Amplitude(0.0), AudioTween::default());
+        return;
+    };
+
+    let track = paddle.team.track();
+    let offset = (ball_transform.translation - paddle_transform.translation).dot(track);
+    let octaves = offset / (arena.height / 2.0);
+    let volume = if ball.is_fired() { CUE_VOLUME } else { 0.0 };
+
+    instance.set_volume(Volume::Amplitude(volume), AudioTween::default());
+    instance.set_playback_rate(2.0_f64.powf(octaves as f64), AudioTween::default());
+    instance.set_panning(
+        panning_for(&arena, ball_transform.translation),
+        AudioTween::default(),
+    );
+}
+
+// 0.0 is hard left, 0.5 is the centre line and 1.0 is hard right
+fn panning_for(arena: &ArenaConfig, position: Vec3) -> f64 {
+    (position.x / arena.width + 0.5).clamp(0.0, 1.0) as f64
+}
+
+fn playback_rate_for(speed: f32) -> f64 {
+    let octaves = (speed - INITIAL_BALL_SPEED) / OCTAVE_SPEED;
+
+    (2.0_f64.powf(octaves as f64)).clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE)
 }
@@ -1,23 +1,89 @@
 use bevy::prelude::*;
-use bevy_ecs_ldtk::prelude::*;
-use bevy_kira_audio::Audio;
 
 use crate::{
-    player::{Paddle, PlayerType, PADDLE_HEIGHT},
-    ARENA_HEIGHT, ARENA_WIDTH, audio::{play_bounce, play_hit},
+    arena::{ArenaConfig, Walls},
+    ascii::{AsciiArt, GLYPH_BALL},
+    events::{GoalScored, PaddleHit, WallBounce},
+    player::{Paddle, PlayerType},
+    rng::GameRng,
+    settings::{GameMode, Settings},
+    theme::Theme,
 };
 
 pub const BALL_SIZE: f32 = 10.0;
-const BALL_START_POSITION_PLAYER_2: Vec3 = Vec3::new(-300.0, 0.0, 0.0);
-const BALL_START_POSITION_PLAYER_1: Vec3 = Vec3::new(300.0, 0.0, 0.0);
-const INITIAL_BALL_SPEED: f32 = 200.0;
+pub const INITIAL_BALL_SPEED: f32 = 200.0;
 const SPEED_INCREMENT: f32 = 50.0;
+// Spin a moving paddle puts on the ball, in radians per second for each unit of paddle speed
+const SPIN_TRANSFER: f32 = 0.05;
+// Radians per second the ball's path turns by for each radian per second of spin
+const MAGNUS_FACTOR: f32 = 0.08;
+// How quickly the ball's spin dies away, per second
+const SPIN_DECAY: f32 = 0.8;
+// Clockwise from the left, the order the serve passes round the players in
+const SERVE_ORDER: [PlayerType; 4] = [
+    PlayerType::Player1,
+    PlayerType::Player3,
+    PlayerType::Player2,
+    PlayerType::Player4,
+];
+// Doubles alternates the serve between the teams
+const DOUBLES_SERVE_ORDER: [PlayerType; 4] = [
+    PlayerType::Player1,
+    PlayerType::Player2,
+    PlayerType::Player3,
+    PlayerType::Player4,
+];
 
 #[derive(Component)]
 pub struct Ball {
     velocity: Vec3,
     fired: bool,
     owner: PlayerType,
+    // Team of the owner, see `Paddle::team`
+    team: PlayerType,
+    // Radians per second the ball rotates by, anticlockwise when positive. A spinning ball's
+    // path curves the same way it spins.
+    spin: f32,
+    // Multiplies the ball's speed until it's next hit
+    boost: f32,
+}
+
+impl Ball {
+    pub fn speed(&self) -> f32 {
+        self.velocity().length()
+    }
+
+    pub fn velocity(&self) -> Vec3 {
+        self.velocity * self.boost
+    }
+
+    pub fn spin(&self) -> f32 {
+        self.spin
+    }
+
+    // Replaces the spin from the last paddle hit, it fades out over time
+    pub fn set_spin(&mut self, spin: f32) {
+        self.spin = spin;
+    }
+
+    pub fn set_boost(&mut self, boost: f32) {
+        self.boost = boost;
+    }
+
+    pub fn is_fired(&self) -> bool {
+        self.fired
+    }
+
+    // The player who served the ball, or last hit it once it's in play
+    pub fn owner(&self) -> PlayerType {
+        self.owner
+    }
+}
+
+// Number of paddle hits since the ball was last served
+#[derive(Resource, Default)]
+pub struct Rally {
+    pub hits: u32,
 }
 
 #[derive(Resource)]
@@ -32,110 +98,164 @@ impl Plugin for BallPlugin {
         app.insert_resource(LastOwner {
             owner: PlayerType::Player1,
         })
-        .add_systems(Startup, spawn_ball)
-        .add_systems(Update, move_ball);
+        .insert_resource(Rally::default())
+        // The ball itself is spawned by `RoundPlugin` when each round is ready to serve
+        .add_systems(Startup, choose_first_server)
+        .add_systems(Update, (move_ball, reset_rally));
     }
 }
-fn move_ball(
+// Public so systems reacting to paddle hits can change the ball straight after
+#[allow(clippy::too_many_arguments)]
+pub fn move_ball(
     mut ball_query: Query<(&mut Ball, &mut Transform), Without<Paddle>>,
     paddle_query: Query<(&Paddle, &Transform)>,
-    asset_server: Res<AssetServer>, audio: Res<Audio>,
-    lastOwner: Res<LastOwner>,
+    arena: Res<ArenaConfig>,
+    walls: Res<Walls>,
+    settings: Res<Settings>,
+    mut rally: ResMut<Rally>,
+    mut paddle_hits: EventWriter<PaddleHit>,
+    mut wall_bounces: EventWriter<WallBounce>,
     time: Res<Time>,
 ) {
-    let (mut ball, mut ball_transform) = ball_query.single_mut();
+    let Ok((mut ball, mut ball_transform)) = ball_query.get_single_mut() else {
+        return;
+    };
 
     if !ball.fired {
         for (paddle, paddle_transform) in paddle_query.iter() {
-            match lastOwner.owner {
-                PlayerType::Player1 => {
-                    match paddle.player_type {
-                        PlayerType::Player1 => {
-                            // Increment velocity for Paddle 1
-                            ball_transform.translation.x = paddle_transform.translation.x + 10.0;
-                            ball_transform.translation.y = paddle_transform.translation.y + 2.0;
-                        }
-                        PlayerType::Player2 => {}
-                    }
-                }
-                PlayerType::Player2 => {
-                    match paddle.player_type {
-                        PlayerType::Player1 => {}
-                        PlayerType::Player2 => {
-                            // Decrement velocity for Paddle 2
-                            ball_transform.translation.x = paddle_transform.translation.x - 10.0;
-                            ball_transform.translation.y = paddle_transform.translation.y - 2.0;
-                        }
-                    }
-                }
+            if paddle.player_type == ball.owner {
+                ball_transform.translation = serve_position(paddle, paddle_transform);
             }
         }
 
         return;
     }
 
-    ball_transform.translation += ball.velocity * time.delta_seconds();
-
-    // Check for collision with the top and bottom walls
-    if ball_transform.translation.y > ARENA_HEIGHT / 2.0 - BALL_SIZE / 2.0 {
-        play_hit(&asset_server, &audio);
-        ball_transform.translation.y = ARENA_HEIGHT / 2.0 - BALL_SIZE / 2.0;
-        ball.velocity.y = -ball.velocity.y;
-    } else if ball_transform.translation.y < -ARENA_HEIGHT / 2.0 + BALL_SIZE / 2.0 {
-        play_hit(&asset_server, &audio);
-        ball_transform.translation.y = -ARENA_HEIGHT / 2.0 + BALL_SIZE / 2.0;
-        ball.velocity.y = -ball.velocity.y;
+    // A spinning ball turns a little every frame rather than travelling in a straight line,
+    // and the sprite turns with it so the spin can be seen
+    if ball.spin != 0.0 {
+        let spin = ball.spin * time.delta_seconds();
+
+        ball.velocity = Quat::from_rotation_z(spin * MAGNUS_FACTOR) * ball.velocity;
+        ball_transform.rotate_z(spin);
+        ball.spin *= (-SPIN_DECAY * time.delta_seconds()).exp();
     }
 
-    for (paddle, paddle_transform) in paddle_query.iter() {
-        if check_paddle_collision(&ball_transform, paddle_transform) {
-            ball.velocity.x = -ball.velocity.x;
+    ball_transform.translation += ball.velocity() * time.delta_seconds();
 
-            match paddle.player_type {
-                PlayerType::Player1 => {
-                    // Increment velocity for Paddle 1
-                    ball.velocity += Vec3::new(SPEED_INCREMENT, 0.0, 0.0);
+    // Check for collision with whichever sides are walls
+    for player in PlayerType::ALL {
+        if !walls.is_wall(player) {
+            continue;
+        }
 
-                    // Determine the paddle's movement direction
-                    bounce_ball(&mut ball, paddle, paddle_transform);
+        let inward = player.inward();
+        let half_extent = if player.is_horizontal() {
+            arena.height / 2.0
+        } else {
+            arena.width / 2.0
+        } - BALL_SIZE / 2.0;
+        let overshoot = ball_transform.translation.dot(-inward) - half_extent;
 
-                    play_bounce(&asset_server, &audio);
-                }
-                PlayerType::Player2 => {
-                    // Decrement velocity for Paddle 2
-                    ball.velocity -= Vec3::new(SPEED_INCREMENT, 0.0, 0.0);
+        if overshoot > 0.0 {
+            ball_transform.translation += inward * overshoot;
+            let reflected = inward * 2.0 * ball.velocity.dot(inward);
+            ball.velocity -= reflected;
+            // The bounce mirrors the path, so it curves the other way
+            ball.spin = -ball.spin;
+            wall_bounces.send(WallBounce {
+                speed: ball.speed(),
+                position: ball_transform.translation,
+            });
+        }
+    }
 
-                    bounce_ball(&mut ball, paddle, paddle_transform);
+    // In doubles a team can't play the ball twice before the other side returns it, unless
+    // double hits are allowed
+    let one_hit_per_team = settings.mode == GameMode::Doubles && !settings.allow_double_hits;
 
-                    play_bounce(&asset_server, &audio);
-                }
-            }
-            break; // Break after detecting any collision to avoid double bounces
-        }
+    // Only a ball heading for the goal behind a paddle is hit back. Paddles can be stacked
+    // one behind the other, the ball bounces off the closest one it touches.
+    let hit = paddle_query
+        .iter()
+        .filter(|(paddle, paddle_transform)| {
+            ball.velocity.dot(paddle.team.inward()) < 0.0
+                && !(one_hit_per_team && ball.team == paddle.team)
+                && check_paddle_collision(&ball_transform, paddle_transform, paddle.size(&arena))
+        })
+        .min_by(|(_, a), (_, b)| {
+            let distance =
+                |transform: &Transform| transform.translation.distance(ball_transform.translation);
+            distance(a).total_cmp(&distance(b))
+        });
+
+    if let Some((paddle, paddle_transform)) = hit {
+        let inward = paddle.team.inward();
+
+        let reflected = inward * 2.0 * ball.velocity.dot(inward);
+        ball.velocity -= reflected;
+        ball.owner = paddle.player_type;
+        ball.team = paddle.team;
+        ball.boost = 1.0;
+        rally.hits += 1;
+
+        // Speed the ball up away from the paddle
+        ball.velocity += inward * SPEED_INCREMENT * paddle.hit_power;
+
+        // Determine the paddle's movement direction
+        bounce_ball(&mut ball, paddle);
+
+        let contact_offset = (ball_transform.translation - paddle_transform.translation)
+            .dot(paddle.team.track())
+            / (paddle.length / 2.0);
+        paddle_hits.send(PaddleHit {
+            player: paddle.player_type,
+            contact_offset: contact_offset.clamp(-1.0, 1.0),
+            speed: ball.speed(),
+            position: ball_transform.translation,
+        });
+    }
+}
+
+fn reset_rally(mut goals: EventReader<GoalScored>, mut rally: ResMut<Rally>) {
+    if !goals.is_empty() {
+        goals.clear();
+        rally.hits = 0;
     }
 }
 
+fn choose_first_server(mut rng: ResMut<GameRng>, mut last_owner: ResMut<LastOwner>) {
+    last_owner.owner = if rng.chance(0.5) {
+        PlayerType::Player1
+    } else {
+        PlayerType::Player2
+    };
+}
+
 pub fn fire_ball(ball: &mut Ball) {
     ball.fired = true;
 }
 
-fn bounce_ball(ball: &mut Ball, paddle: &Paddle, paddle_transform: &Transform) {
-    let y_velocity_adjustment = 0.5 * paddle.speed; // Adjust this factor as needed
+fn bounce_ball(ball: &mut Ball, paddle: &Paddle) {
+    // A faster paddle pushes the ball further along with it
+    let velocity_adjustment = 0.5 * paddle.velocity; // Adjust this factor as needed
+    let track = paddle.team.track();
 
-    if paddle.y_velocity > 0.0 {
-        // Paddle moving up
-        ball.velocity.y += y_velocity_adjustment;
-    } else if paddle.y_velocity < 0.0 {
-        // Paddle moving down
-        ball.velocity.y -= y_velocity_adjustment;
-    }
+    // The paddle's face drags the side of the ball touching it along with it, so the ball
+    // spins and curves back against the way the paddle was moving. A still paddle takes the
+    // spin off.
+    let contact_side = Vec3::Z.cross(-paddle.team.inward());
+    ball.spin = paddle.velocity * track.dot(contact_side) * SPIN_TRANSFER;
+
+    ball.velocity += track * velocity_adjustment;
 }
 
 // Helper function to check collision with a paddle
-fn check_paddle_collision(ball_transform: &Transform, paddle_transform: &Transform) -> bool {
-    // Define the paddle size (adjust these values according to your paddle size)
-    let paddle_size = Vec2::new(10.0, PADDLE_HEIGHT);
-
+fn check_paddle_collision(
+    ball_transform: &Transform,
+    paddle_transform: &Transform,
+    paddle_size: Vec2,
+) -> bool {
     let ball_pos = ball_transform.translation;
     let paddle_pos = paddle_transform.translation;
 
@@ -146,52 +266,63 @@ fn check_paddle_collision(ball_transform: &Transform, paddle_transform: &Transfo
         && ball_pos.y - BALL_SIZE / 2.0 < paddle_pos.y + paddle_size.y / 2.0
 }
 
-pub fn spawn_ball(mut commands: Commands, mut lastOwner: ResMut<LastOwner>) {
-    match lastOwner.owner {
-        PlayerType::Player1 => {
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        custom_size: Some(Vec2::new(BALL_SIZE, BALL_SIZE)),
-                        ..default()
-                    },
-                    transform: Transform {
-                        translation: Vec3::new(-1000.0, -1000.0, 0.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                Ball {
-                    velocity: Vec3::new(INITIAL_BALL_SPEED, 0.0, 0.0),
-                    fired: false,
-                    owner: PlayerType::Player2,
-                },
-            ));
-
-            lastOwner.owner = PlayerType::Player2;
-        }
-        PlayerType::Player2 => {
-            commands.spawn((
-                SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::WHITE,
-                        custom_size: Some(Vec2::new(BALL_SIZE, BALL_SIZE)),
-                        ..default()
-                    },
-                    transform: Transform {
-                        translation: Vec3::new(-1000.0, -1000.0, 0.0),
-                        ..default()
-                    },
-                    ..default()
-                },
-                Ball {
-                    velocity: Vec3::new(INITIAL_BALL_SPEED, 0.0, 0.0),
-                    fired: false,
-                    owner: PlayerType::Player1,
-                },
-            ));
-            lastOwner.owner = PlayerType::Player1;
-        }
-    }
+// The ball sits just in front of the serving paddle, a little off centre
+fn serve_position(paddle: &Paddle, paddle_transform: &Transform) -> Vec3 {
+    let inward = paddle.team.inward();
+    let across = Vec3::new(-inward.y, inward.x, 0.0);
+
+    paddle_transform.translation + inward * 10.0 + across * 2.0
+}
+
+pub fn spawn_ball(
+    mut commands: Commands,
+    mut last_owner: ResMut<LastOwner>,
+    paddle_query: Query<(&Paddle, &Transform)>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+) {
+    let order = if settings.mode == GameMode::Doubles {
+        DOUBLES_SERVE_ORDER
+    } else {
+        SERVE_ORDER
+    };
+
+    // The serve passes round to the next player that's still in
+    let start = order
+        .iter()
+        .position(|player| *player == last_owner.owner)
+        .unwrap_or(0);
+    let Some((server, server_transform)) = (1..=order.len())
+        .map(|step| order[(start + step) % order.len()])
+        .find_map(|player| {
+            paddle_query
+                .iter()
+                .find(|(paddle, _)| paddle.player_type == player)
+        })
+    else {
+        return;
+    };
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: theme.ball,
+                custom_size: Some(Vec2::new(BALL_SIZE, BALL_SIZE)),
+                ..default()
+            },
+            transform: Transform::from_translation(serve_position(server, server_transform)),
+            ..default()
+        },
+        Ball {
+            velocity: server.team.inward() * INITIAL_BALL_SPEED,
+            fired: false,
+            owner: server.player_type,
+            team: server.team,
+            spin: 0.0,
+            boost: 1.0,
+        },
+        AsciiArt { glyph: GLYPH_BALL },
+    ));
+
+    last_owner.owner = server.player_type;
 }
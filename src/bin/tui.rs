@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use bevy::{
+    app::ScheduleRunnerPlugin, asset::AssetPlugin, hierarchy::HierarchyPlugin, input::InputPlugin,
+    prelude::*, transform::TransformPlugin,
+};
+
+use game_tut::{
+    settings::load_settings_or_exit,
+    tui::{enter_terminal, leave_terminal, TuiPlugin},
+    GamePlugins,
+};
+
+fn main() -> std::io::Result<()> {
+    // Window settings are ignored, but the match settings apply in the terminal too
+    let settings = load_settings_or_exit();
+    let enhanced_keys = enter_terminal()?;
+
+    // No window, renderer or logging, the terminal is drawn to directly by `TuiPlugin`
+    App::new()
+        .add_plugins(
+            MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+                1.0 / 60.0,
+            ))),
+        )
+        .add_plugins((
+            AssetPlugin::default(),
+            InputPlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+        ))
+        .add_plugins(GamePlugins { settings })
+        .add_plugins(TuiPlugin { enhanced_keys })
+        .run();
+
+    leave_terminal(enhanced_keys)
+}
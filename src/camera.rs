@@ -0,0 +1,83 @@
+use bevy::{
+    prelude::*,
+    render::camera::{ScalingMode, Viewport},
+    window::{PrimaryWindow, WindowMode},
+};
+
+use crate::arena::ArenaConfig;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_camera)
+            .add_systems(Update, (toggle_fullscreen, fit_camera_to_window));
+    }
+}
+
+fn spawn_camera(mut commands: Commands, arena: Res<ArenaConfig>) {
+    let mut camera = Camera2dBundle::default();
+    let view_size = arena.view_size();
+
+    camera.projection.scaling_mode = ScalingMode::Fixed {
+        width: view_size.x,
+        height: view_size.y,
+    };
+
+    commands.spawn(camera);
+}
+
+fn toggle_fullscreen(
+    keyboard: Res<Input<KeyCode>>,
+    mut window_query: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !keyboard.just_pressed(KeyCode::F11) {
+        return;
+    }
+
+    for mut window in window_query.iter_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+    }
+}
+
+// Letterboxes or pillarboxes the view into the window. When the window is big enough the
+// view is scaled by a whole number so the nearest-filtered sprites stay pixel perfect.
+fn fit_camera_to_window(
+    window_query: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+    mut camera_query: Query<&mut Camera, With<Camera2d>>,
+    arena: Res<ArenaConfig>,
+) {
+    let Ok(window) = window_query.get_single() else {
+        return;
+    };
+
+    let window_size = Vec2::new(
+        window.physical_width() as f32,
+        window.physical_height() as f32,
+    );
+    if window_size.x < 1.0 || window_size.y < 1.0 {
+        // Minimised
+        return;
+    }
+
+    // The arena plus room for the scoreboard must always be on screen
+    let view_size = arena.view_size();
+    let mut scale = (window_size.x / view_size.x).min(window_size.y / view_size.y);
+    if scale >= 1.0 {
+        scale = scale.floor();
+    }
+
+    let viewport_size = (view_size * scale).round().min(window_size);
+    let viewport_position = ((window_size - viewport_size) / 2.0).floor();
+
+    for mut camera in camera_query.iter_mut() {
+        camera.viewport = Some(Viewport {
+            physical_position: viewport_position.as_uvec2(),
+            physical_size: viewport_size.as_uvec2(),
+            ..default()
+        });
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    arena::ArenaConfig,
+    ascii::{SpriteStyleSet, StandardOnly},
+    files::read_asset_ron,
+    player::{Handling, Paddle, PlayerType},
+};
+
+// In the assets folder
+const CHARACTERS_PATH: &str = "characters.ron";
+
+// Size of the character sprite drawn behind each paddle
+const SPRITE_SIZE: f32 = 32.0;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Special {
+    #[default]
+    None,
+    // Bends the ball's path as it travels
+    CurveShot,
+    // Doubles the ball's speed until it's next hit
+    Smash,
+    // A short burst of paddle speed
+    Dash,
+}
+
+impl Special {
+    pub fn name(self) -> &'static str {
+        match self {
+            Special::None => "no special",
+            Special::CurveShot => "curve shot",
+            Special::Smash => "smash",
+            Special::Dash => "dash",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Character {
+    pub name: String,
+    // Path in the assets folder, the paddle is left plain without one
+    pub sprite: Option<String>,
+    pub paddle_speed: f32,
+    // Multiplies the arena's paddle height
+    pub paddle_scale: f32,
+    // Multiplies how much each return speeds the ball up
+    pub hit_power: f32,
+    pub special: Special,
+    // Only used with momentum paddle physics
+    #[serde(default)]
+    pub handling: Handling,
+}
+
+impl Default for Character {
+    fn default() -> Self {
+        Character {
+            name: "Classic".into(),
+            sprite: None,
+            paddle_speed: 300.0,
+            paddle_scale: 1.0,
+            hit_power: 1.0,
+            special: Special::None,
+            handling: Handling::default(),
+        }
+    }
+}
+
+impl Character {
+    // Checks the character can be played in the arena. Its paddle has to fit along every
+    // side's track, the ends of a flat paddle also have to stay clear of the side paddles.
+    fn check(&self, arena: &ArenaConfig) -> Result<(), String> {
+        for (field, value) in [
+            ("paddle_speed", self.paddle_speed),
+            ("paddle_scale", self.paddle_scale),
+            ("hit_power", self.hit_power),
+        ] {
+            if value <= 0.0 {
+                return Err(format!("{field} must be more than 0, got {value}"));
+            }
+        }
+
+        let length = arena.paddle_height * self.paddle_scale;
+        if PlayerType::ALL
+            .into_iter()
+            .any(|player| arena.track_limit(player, length) < 0.0)
+        {
+            return Err(format!(
+                "paddle_scale {} makes the paddle too long for the arena",
+                self.paddle_scale
+            ));
+        }
+
+        Ok(())
+    }
+
+    // One line summary for the character select screen
+    pub fn description(&self) -> String {
+        format!(
+            "speed {:.0}  size {:.2}  power {:.2}  {}",
+            self.paddle_speed,
+            self.paddle_scale,
+            self.hit_power,
+            self.special.name()
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct CharacterFile {
+    characters: Vec<Character>,
+}
+
+// Every character from the characters file, the first is picked by default
+#[derive(Resource, Clone, Debug)]
+pub struct Characters(pub Vec<Character>);
+
+impl Characters {
+    pub fn get(&self, index: usize) -> &Character {
+        &self.0[index.min(self.0.len() - 1)]
+    }
+}
+
+// The character each player has picked, by index into `Characters`
+#[derive(Resource, Default, Debug)]
+pub struct CharacterChoices {
+    choices: HashMap<PlayerType, usize>,
+}
+
+impl CharacterChoices {
+    pub fn get(&self, player: PlayerType) -> usize {
+        self.choices.get(&player).copied().unwrap_or_default()
+    }
+
+    // Moves the player on to the next character, back to the first after the last
+    pub fn cycle(&mut self, player: PlayerType, count: usize) {
+        let next = (self.get(player) + 1) % count.max(1);
+        self.choices.insert(player, next);
+    }
+}
+
+// Drawn behind a paddle for its player's character
+#[derive(Component)]
+struct CharacterSprite;
+
+pub struct CharactersPlugin;
+
+impl Plugin for CharactersPlugin {
+    fn build(&self, app: &mut App) {
+        let mut characters = read_asset_ron::<CharacterFile>(CHARACTERS_PATH)
+            .map(|file| file.characters)
+            .unwrap_or_else(|err| {
+                warn!("{err}, using the classic paddle");
+                Vec::new()
+            });
+
+        for character in characters.iter_mut() {
+            if let Err(reason) = character.handling.check() {
+                warn!(
+                    "Bad handling for {:?} in {CHARACTERS_PATH}, using the default: {reason}",
+                    character.name
+                );
+                character.handling = Handling::default();
+            }
+        }
+
+        // Needs `ArenaPlugin` to have been added first
+        let arena = app.world.resource::<ArenaConfig>();
+        let characters: Vec<Character> = characters
+            .into_iter()
+            .filter(|character| match character.check(arena) {
+                Ok(()) => true,
+                Err(reason) => {
+                    warn!(
+                        "Skipping {:?} in {CHARACTERS_PATH}: {reason}",
+                        character.name
+                    );
+                    false
+                }
+            })
+            .collect();
+        let characters = if characters.is_empty() {
+            vec![Character::default()]
+        } else {
+            characters
+        };
+
+        app.insert_resource(Characters(characters))
+            .insert_resource(CharacterChoices::default())
+            .add_systems(Update, apply_characters.in_set(SpriteStyleSet));
+    }
+}
+
+// Gives each paddle its player's character when it's spawned, or when the choice changes
+fn apply_characters(
+    mut commands: Commands,
+    mut paddle_query: Query<(Entity, &mut Paddle, &mut Sprite, Option<&Children>)>,
+    sprite_query: Query<(), With<CharacterSprite>>,
+    characters: Res<Characters>,
+    choices: Res<CharacterChoices>,
+    arena: Res<ArenaConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, mut paddle, mut sprite, children) in paddle_query.iter_mut() {
+        if !paddle.is_added() && !choices.is_changed() {
+            continue;
+        }
+
+        let character = characters.get(choices.get(paddle.player_type));
+
+        paddle.speed = character.paddle_speed;
+        paddle.length = arena.paddle_height * character.paddle_scale;
+        paddle.hit_power = character.hit_power;
+        paddle.special = character.special;
+        paddle.handling = character.handling;
+        sprite.custom_size = Some(paddle.size(&arena));
+
+        for child in children.into_iter().flatten() {
+            if sprite_query.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+
+        let Some(path) = &character.sprite else {
+            continue;
+        };
+
+        // Just behind the paddle, on the side of the goal it guards
+        let offset = -paddle.team.inward() * (arena.paddle_width / 2.0 + SPRITE_SIZE / 2.0);
+        let decoration = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        custom_size: Some(Vec2::splat(SPRITE_SIZE)),
+                        ..default()
+                    },
+                    texture: asset_server.load(path.as_str()),
+                    transform: Transform::from_translation(offset),
+                    ..default()
+                },
+                CharacterSprite,
+                StandardOnly,
+            ))
+            .id();
+        commands.entity(entity).add_child(decoration);
+    }
+}
@@ -0,0 +1,272 @@
+use bevy::{
+    core_pipeline::{core_2d, fullscreen_vertex_shader::fullscreen_shader_vertex_state},
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponentPlugin, UniformComponentPlugin,
+        },
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, ViewNode, ViewNodeRunner,
+        },
+        render_resource::*,
+        renderer::{RenderContext, RenderDevice},
+        texture::BevyDefault,
+        view::ViewTarget,
+        RenderApp,
+    },
+};
+
+use crate::settings::Settings;
+
+const CRT_SHADER_PATH: &str = "shaders/crt.wgsl";
+
+// The ShaderType derive generates an unused `check` function for each field's type as a separate
+// item next to the struct, which newer compilers report as dead code. An allow on the struct or
+// its fields doesn't reach those items, so the struct sits alone in a module that allows it.
+#[allow(dead_code)]
+mod uniform {
+    use bevy::{
+        prelude::*,
+        render::{extract_component::ExtractComponent, render_resource::ShaderType},
+    };
+
+    // Strength of each part of the CRT look, copied to the shader for any camera that has it
+    #[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+    pub(super) struct CrtSettings {
+        pub(super) scanlines: f32,
+        pub(super) curvature: f32,
+        pub(super) bloom: f32,
+        pub(super) aberration: f32,
+    }
+}
+
+use uniform::CrtSettings;
+
+// Draws the arena as if on an old CRT monitor, as a post-processing pass on the 2D camera.
+// It's turned on in the settings, and F3 switches it on and off while playing.
+pub struct CrtPlugin;
+
+impl Plugin for CrtPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<CrtSettings>::default(),
+            UniformComponentPlugin::<CrtSettings>::default(),
+        ))
+        .add_systems(Update, (toggle_crt, apply_crt_settings).chain());
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        // After tonemapping, so the shader works on the final colours
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<CrtNode>>(core_2d::graph::NAME, CrtNode::NAME)
+            .add_render_graph_edges(
+                core_2d::graph::NAME,
+                &[
+                    core_2d::graph::node::TONEMAPPING,
+                    CrtNode::NAME,
+                    core_2d::graph::node::END_MAIN_PASS_POST_PROCESSING,
+                ],
+            );
+    }
+
+    // The pipeline needs the render device, which only exists once the renderer has started
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<CrtPipeline>();
+    }
+}
+
+fn toggle_crt(keyboard: Res<Input<KeyCode>>, mut settings: ResMut<Settings>) {
+    if keyboard.just_pressed(KeyCode::F3) {
+        settings.crt = !settings.crt;
+    }
+}
+
+// Gives the camera the CRT settings when the effect is on, and takes them away when it's off
+fn apply_crt_settings(
+    mut commands: Commands,
+    camera_query: Query<Entity, With<Camera2d>>,
+    added_query: Query<(), Added<Camera2d>>,
+    settings: Res<Settings>,
+) {
+    if !settings.is_changed() && added_query.is_empty() {
+        return;
+    }
+
+    for entity in camera_query.iter() {
+        if settings.crt {
+            commands.entity(entity).insert(CrtSettings {
+                scanlines: settings.crt_scanlines,
+                curvature: settings.crt_curvature,
+                bloom: settings.crt_bloom,
+                aberration: settings.crt_aberration,
+            });
+        } else {
+            commands.entity(entity).remove::<CrtSettings>();
+        }
+    }
+}
+
+#[derive(Default)]
+struct CrtNode;
+
+impl CrtNode {
+    const NAME: &str = "crt";
+}
+
+impl ViewNode for CrtNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static DynamicUniformIndex<CrtSettings>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, settings_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let crt_pipeline = world.resource::<CrtPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        // Nothing is drawn until the shader has loaded and compiled
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(crt_pipeline.pipeline_id) else {
+            return Ok(());
+        };
+        let settings_uniforms = world.resource::<ComponentUniforms<CrtSettings>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        // Reads what's been drawn so far and writes the CRT version over it
+        let post_process = view_target.post_process_write();
+
+        let bind_group = render_context
+            .render_device()
+            .create_bind_group(&BindGroupDescriptor {
+                label: Some("crt_bind_group"),
+                layout: &crt_pipeline.layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(post_process.source),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&crt_pipeline.sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: settings_binding,
+                    },
+                ],
+            });
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("crt_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct CrtPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline_id: CachedRenderPipelineId,
+}
+
+impl FromWorld for CrtPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let layout = render_device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("crt_bind_group_layout"),
+            entries: &[
+                // What the camera has drawn
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: Some(CrtSettings::min_size()),
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        // Linear filtering keeps the curved picture smooth
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let shader = world.resource::<AssetServer>().load(CRT_SHADER_PATH);
+
+        let pipeline_id =
+            world
+                .resource_mut::<PipelineCache>()
+                .queue_render_pipeline(RenderPipelineDescriptor {
+                    label: Some("crt_pipeline".into()),
+                    layout: vec![layout.clone()],
+                    vertex: fullscreen_shader_vertex_state(),
+                    fragment: Some(FragmentState {
+                        shader,
+                        shader_defs: vec![],
+                        entry_point: "fragment".into(),
+                        targets: vec![Some(ColorTargetState {
+                            format: TextureFormat::bevy_default(),
+                            blend: None,
+                            write_mask: ColorWrites::ALL,
+                        })],
+                    }),
+                    primitive: PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: MultisampleState::default(),
+                    push_constant_ranges: vec![],
+                });
+
+        CrtPipeline {
+            layout,
+            sampler,
+            pipeline_id,
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+
+use crate::{
+    ascii::DisplayMode,
+    ball::{Ball, BALL_SIZE, INITIAL_BALL_SPEED},
+    events::{GoalScored, PaddleHit, WallBounce},
+    player::Paddle,
+    settings::Settings,
+    theme::Theme,
+};
+
+// Seconds a trail dot takes to fade at the serve speed, faster balls leave longer trails
+const TRAIL_SECONDS: f32 = 0.12;
+const MAX_TRAIL_SECONDS: f32 = 0.4;
+
+const PARTICLES_PER_HIT: usize = 12;
+const PARTICLES_PER_BOUNCE: usize = 6;
+const PARTICLE_SIZE: f32 = 3.0;
+const PARTICLE_SPEED: f32 = 160.0;
+const PARTICLE_SECONDS: f32 = 0.35;
+
+const SHAKE_SECONDS: f32 = 0.35;
+// Furthest the camera moves from the middle at the start of a shake
+const SHAKE_DISTANCE: f32 = 8.0;
+
+const SQUASH_SECONDS: f32 = 0.15;
+// How much thinner a paddle gets the moment it's hit, it gets half as much longer
+const SQUASH_AMOUNT: f32 = 0.4;
+
+// Fades out and shrinks where the ball has been
+#[derive(Component)]
+struct TrailDot {
+    seconds: f32,
+    lifetime: f32,
+}
+
+#[derive(Component)]
+struct Particle {
+    velocity: Vec3,
+    seconds: f32,
+}
+
+// Seconds left of squashing a paddle after it hits the ball
+#[derive(Component)]
+struct Squash {
+    seconds: f32,
+}
+
+#[derive(Resource, Default)]
+struct ScreenShake {
+    seconds: f32,
+}
+
+// Extra feedback for what happens in a match, for the windowed game only. Each effect can be
+// turned off in the settings.
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ScreenShake::default()).add_systems(
+            Update,
+            (
+                spawn_trail,
+                spawn_particles,
+                start_screen_shake,
+                start_squash,
+                fade_trail,
+                move_particles,
+                shake_camera,
+                squash_paddles,
+            ),
+        );
+    }
+}
+
+fn spawn_trail(
+    mut commands: Commands,
+    ball_query: Query<(&Ball, &Transform)>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    mode: Res<State<DisplayMode>>,
+) {
+    if !settings.ball_trail || *mode.get() == DisplayMode::Ascii {
+        return;
+    }
+
+    for (ball, transform) in ball_query.iter() {
+        if !ball.is_fired() {
+            continue;
+        }
+
+        let lifetime = (TRAIL_SECONDS * ball.speed() / INITIAL_BALL_SPEED).min(MAX_TRAIL_SECONDS);
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.ball.with_a(0.5),
+                    custom_size: Some(Vec2::splat(BALL_SIZE)),
+                    ..default()
+                },
+                // Behind the ball
+                transform: Transform::from_translation(transform.translation - Vec3::Z),
+                ..default()
+            },
+            TrailDot {
+                seconds: lifetime,
+                lifetime,
+            },
+        ));
+    }
+}
+
+fn fade_trail(
+    mut commands: Commands,
+    mut trail_query: Query<(Entity, &mut TrailDot, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut dot, mut sprite) in trail_query.iter_mut() {
+        dot.seconds -= time.delta_seconds();
+        if dot.seconds <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let left = dot.seconds / dot.lifetime;
+        sprite.color.set_a(0.5 * left);
+        sprite.custom_size = Some(Vec2::splat(BALL_SIZE * left));
+    }
+}
+
+// A burst of sparks flying out from wherever the ball hits a paddle or a wall
+fn spawn_particles(
+    mut commands: Commands,
+    mut paddle_hits: EventReader<PaddleHit>,
+    mut wall_bounces: EventReader<WallBounce>,
+    settings: Res<Settings>,
+    theme: Res<Theme>,
+    mode: Res<State<DisplayMode>>,
+) {
+    if !settings.hit_particles || *mode.get() == DisplayMode::Ascii {
+        paddle_hits.clear();
+        wall_bounces.clear();
+        return;
+    }
+
+    let bursts = paddle_hits
+        .iter()
+        .map(|hit| (hit.position, PARTICLES_PER_HIT))
+        .chain(
+            wall_bounces
+                .iter()
+                .map(|bounce| (bounce.position, PARTICLES_PER_BOUNCE)),
+        );
+
+    for (position, count) in bursts {
+        for index in 0..count {
+            // Spread evenly round the circle, alternating between fast and slow
+            let angle = TAU * index as f32 / count as f32;
+            let speed = PARTICLE_SPEED * if index % 2 == 0 { 1.0 } else { 0.6 };
+
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: theme.ball,
+                        custom_size: Some(Vec2::splat(PARTICLE_SIZE)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(position),
+                    ..default()
+                },
+                Particle {
+                    velocity: Vec3::new(angle.cos(), angle.sin(), 0.0) * speed,
+                    seconds: PARTICLE_SECONDS,
+                },
+            ));
+        }
+    }
+}
+
+fn move_particles(
+    mut commands: Commands,
+    mut particle_query: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut transform, mut sprite) in particle_query.iter_mut() {
+        particle.seconds -= time.delta_seconds();
+        if particle.seconds <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += particle.velocity * time.delta_seconds();
+        sprite.color.set_a(particle.seconds / PARTICLE_SECONDS);
+    }
+}
+
+fn start_screen_shake(
+    mut goals: EventReader<GoalScored>,
+    mut shake: ResMut<ScreenShake>,
+    settings: Res<Settings>,
+) {
+    if goals.is_empty() {
+        return;
+    }
+    goals.clear();
+
+    if settings.screen_shake {
+        shake.seconds = SHAKE_SECONDS;
+    }
+}
+
+// Jolts the camera around the middle of the arena, settling as the shake runs out
+fn shake_camera(
+    mut camera_query: Query<&mut Transform, With<Camera2d>>,
+    mut shake: ResMut<ScreenShake>,
+    time: Res<Time>,
+) {
+    if shake.seconds <= 0.0 {
+        return;
+    }
+    shake.seconds = (shake.seconds - time.delta_seconds()).max(0.0);
+
+    let distance = SHAKE_DISTANCE * shake.seconds / SHAKE_SECONDS;
+    let t = time.elapsed_seconds();
+    let offset = Vec2::new((t * 53.0).sin(), (t * 47.0).cos()) * distance;
+
+    for mut transform in camera_query.iter_mut() {
+        transform.translation.x = offset.x;
+        transform.translation.y = offset.y;
+    }
+}
+
+fn start_squash(
+    mut commands: Commands,
+    mut paddle_hits: EventReader<PaddleHit>,
+    paddle_query: Query<(Entity, &Paddle)>,
+    settings: Res<Settings>,
+) {
+    for hit in paddle_hits.iter() {
+        if !settings.paddle_squash {
+            continue;
+        }
+
+        for (entity, paddle) in paddle_query.iter() {
+            if paddle.player_type == hit.player {
+                commands.entity(entity).insert(Squash {
+                    seconds: SQUASH_SECONDS,
+                });
+            }
+        }
+    }
+}
+
+// Flattens the paddle against its goal and stretches it along its track, springing back
+// as the squash runs out
+fn squash_paddles(
+    mut commands: Commands,
+    mut paddle_query: Query<(Entity, &Paddle, &mut Squash, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, paddle, mut squash, mut transform) in paddle_query.iter_mut() {
+        squash.seconds -= time.delta_seconds();
+        if squash.seconds <= 0.0 {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<Squash>();
+            continue;
+        }
+
+        let amount = SQUASH_AMOUNT * squash.seconds / SQUASH_SECONDS;
+        transform.scale =
+            Vec3::ONE - paddle.team.inward().abs() * amount + paddle.team.track() * amount / 2.0;
+    }
+}
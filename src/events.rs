@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+
+use crate::player::PlayerType;
+
+// Everything that happens during a match is sent as one of these events. Score, audio and
+// the round reset all react to them, so new systems can hook onto gameplay the same way
+// without changing the simulation.
+pub struct GameEventsPlugin;
+
+impl Plugin for GameEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<BallServed>()
+            .add_event::<PaddleHit>()
+            .add_event::<WallBounce>()
+            .add_event::<GoalScored>()
+            .add_event::<MatchWon>();
+    }
+}
+
+// Systems turning goals into points or lost lives, and sending `MatchWon`. Systems reacting
+// to the outcome of a goal run after this set.
+#[derive(SystemSet, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ScoringSet;
+
+#[derive(Event, Clone, Debug)]
+pub struct BallServed {
+    pub server: PlayerType,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct PaddleHit {
+    pub player: PlayerType,
+    // Where the ball hit the paddle, from -1.0 at the bottom edge to 1.0 at the top edge
+    pub contact_offset: f32,
+    // Speed of the ball as it leaves the paddle
+    pub speed: f32,
+    pub position: Vec3,
+}
+
+// The ball bounced off one of the walls
+#[derive(Event, Clone, Debug)]
+pub struct WallBounce {
+    pub speed: f32,
+    pub position: Vec3,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct GoalScored {
+    pub scorer: PlayerType,
+    // The player whose goal the ball went into
+    pub conceded: PlayerType,
+}
+
+// Sent after the goal that takes a player to the points needed to win, or knocks out the
+// last player or team standing against them
+#[derive(Event, Clone, Debug)]
+pub struct MatchWon {
+    pub winner: PlayerType,
+}
@@ -0,0 +1,41 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::{asset::FileAssetIo, prelude::*};
+use serde::{de::DeserializeOwned, Serialize};
+
+// Full path of a file in the assets folder. It's found the same way the asset server finds
+// the folder, so the data files load wherever the game is started from.
+pub fn asset_path(path: &str) -> PathBuf {
+    FileAssetIo::get_base_path().join("assets").join(path)
+}
+
+// Reads a RON data file from the assets folder, the error says which file and what went wrong
+pub fn read_asset_ron<T: DeserializeOwned>(path: &str) -> Result<T, String> {
+    let full_path = asset_path(path);
+    let contents = fs::read_to_string(&full_path)
+        .map_err(|err| format!("Failed to read {}: {err}", full_path.display()))?;
+
+    ron::from_str(&contents)
+        .map_err(|err| format!("Failed to parse {}: {err}", full_path.display()))
+}
+
+// Writes a value as pretty printed JSON, creating the directory it goes in if needed.
+// Failures are warned about with `what` was being saved, and false is returned.
+pub fn write_json<T: Serialize>(path: &Path, value: &T, what: &str) -> bool {
+    let result = serde_json::to_string_pretty(value)
+        .map_err(|err| err.to_string())
+        .and_then(|json| {
+            if let Some(directory) = path.parent() {
+                fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+            }
+            fs::write(path, json).map_err(|err| err.to_string())
+        });
+
+    if let Err(err) = &result {
+        warn!("Failed to save {what} to {}: {err}", path.display());
+    }
+    result.is_ok()
+}
@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::{ArenaConfig, Walls},
+    events::{GoalScored, MatchWon, ScoringSet},
+    player::{spawn_paddle, Lane, Paddle, PlayerType},
+    round::RoundPhase,
+    score::{spawn_goal, GoalFor},
+    settings::{four_player_mode, GameMode, Settings},
+};
+
+// Lives left for each player in the four player modes. A player is out once they have
+// none left, their paddle is removed and their side of the arena becomes a wall.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct Lives {
+    player1: u32,
+    player2: u32,
+    player3: u32,
+    player4: u32,
+}
+
+impl Lives {
+    pub fn new(lives: u32) -> Lives {
+        Lives {
+            player1: lives,
+            player2: lives,
+            player3: lives,
+            player4: lives,
+        }
+    }
+
+    pub fn get(&self, player: PlayerType) -> u32 {
+        match player {
+            PlayerType::Player1 => self.player1,
+            PlayerType::Player2 => self.player2,
+            PlayerType::Player3 => self.player3,
+            PlayerType::Player4 => self.player4,
+        }
+    }
+
+    fn get_mut(&mut self, player: PlayerType) -> &mut u32 {
+        match player {
+            PlayerType::Player1 => &mut self.player1,
+            PlayerType::Player2 => &mut self.player2,
+            PlayerType::Player3 => &mut self.player3,
+            PlayerType::Player4 => &mut self.player4,
+        }
+    }
+
+    pub fn is_out(&self, player: PlayerType) -> bool {
+        self.get(player) == 0
+    }
+
+    // Whether the next life lost could end the match, with someone on their last life whose
+    // exit would leave only one player, or one team, standing
+    pub fn is_match_point(&self, mode: GameMode) -> bool {
+        let survivors: Vec<PlayerType> = PlayerType::ALL
+            .into_iter()
+            .filter(|player| !self.is_out(*player))
+            .collect();
+
+        survivors.iter().any(|player| {
+            let last_standing = match mode {
+                GameMode::Teams => survivors
+                    .iter()
+                    .all(|other| other == player || team(*other) != team(*player)),
+                _ => survivors.len() == 2,
+            };
+            self.get(*player) == 1 && last_standing
+        })
+    }
+
+    // The counter shown on each player's side
+    pub fn label(&self, player: PlayerType) -> String {
+        if self.is_out(player) {
+            "X".to_string()
+        } else {
+            self.get(player).to_string()
+        }
+    }
+}
+
+// The left and top paddles play against the right and bottom ones in the teams mode. Each
+// team is named after the player on its side paddle.
+pub fn team(player: PlayerType) -> PlayerType {
+    match player {
+        PlayerType::Player1 | PlayerType::Player3 => PlayerType::Player1,
+        PlayerType::Player2 | PlayerType::Player4 => PlayerType::Player2,
+    }
+}
+
+pub struct FourPlayerPlugin;
+
+impl Plugin for FourPlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Lives::default())
+            .add_systems(Startup, setup_four_player.run_if(four_player_mode))
+            .add_systems(
+                OnExit(RoundPhase::Menu),
+                start_four_player_match.run_if(four_player_mode),
+            )
+            .add_systems(
+                Update,
+                lose_life.in_set(ScoringSet).run_if(four_player_mode),
+            );
+    }
+}
+
+// The side paddles and goals are there in every mode, the top and bottom ones are added here
+// Each player's lives are shown by `HudPlugin`
+fn setup_four_player(mut commands: Commands, arena: Res<ArenaConfig>) {
+    spawn_goal(&mut commands, &arena, GoalFor::Player3);
+    spawn_goal(&mut commands, &arena, GoalFor::Player4);
+}
+
+// Every player starts a match with full lives, and the paddles knocked out last match return
+fn start_four_player_match(
+    mut commands: Commands,
+    mut lives: ResMut<Lives>,
+    mut walls: ResMut<Walls>,
+    paddle_query: Query<&Paddle>,
+    arena: Res<ArenaConfig>,
+    settings: Res<Settings>,
+) {
+    *lives = Lives::new(settings.lives);
+
+    for player in PlayerType::ALL {
+        walls.set_wall(player, false);
+
+        if !paddle_query
+            .iter()
+            .any(|paddle| paddle.player_type == player)
+        {
+            spawn_paddle(&mut commands, &arena, player, player, Lane::Back);
+        }
+    }
+}
+
+fn lose_life(
+    mut commands: Commands,
+    mut goal_events: EventReader<GoalScored>,
+    mut match_won: EventWriter<MatchWon>,
+    mut lives: ResMut<Lives>,
+    mut walls: ResMut<Walls>,
+    paddle_query: Query<(Entity, &Paddle)>,
+    settings: Res<Settings>,
+) {
+    for goal in goal_events.iter() {
+        let remaining = lives.get_mut(goal.conceded);
+        *remaining = remaining.saturating_sub(1);
+
+        if !lives.is_out(goal.conceded) {
+            continue;
+        }
+
+        for (entity, paddle) in paddle_query.iter() {
+            if paddle.player_type == goal.conceded {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        walls.set_wall(goal.conceded, true);
+
+        let survivors: Vec<PlayerType> = PlayerType::ALL
+            .into_iter()
+            .filter(|player| !lives.is_out(*player))
+            .collect();
+
+        let winner = match settings.mode {
+            GameMode::Teams => {
+                let teams: Vec<PlayerType> = survivors.iter().map(|player| team(*player)).collect();
+                teams.iter().all(|team| *team == teams[0]).then(|| teams[0])
+            }
+            _ => (survivors.len() == 1).then(|| survivors[0]),
+        };
+
+        if let Some(winner) = winner {
+            match_won.send(MatchWon { winner });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_point_needs_the_last_life_to_decide_the_match() {
+        let mut lives = Lives::new(1);
+        assert!(!lives.is_match_point(GameMode::FreeForAll));
+        assert!(!lives.is_match_point(GameMode::Teams));
+
+        // Players 3 and 4 out leaves each team with one player, on their last life
+        *lives.get_mut(PlayerType::Player3) = 0;
+        *lives.get_mut(PlayerType::Player4) = 0;
+        assert!(lives.is_match_point(GameMode::FreeForAll));
+        assert!(lives.is_match_point(GameMode::Teams));
+
+        *lives.get_mut(PlayerType::Player1) = 2;
+        *lives.get_mut(PlayerType::Player2) = 2;
+        assert!(!lives.is_match_point(GameMode::FreeForAll));
+    }
+}
@@ -0,0 +1,257 @@
+use bevy::{
+    a11y::{
+        accesskit::{NodeBuilder, Role},
+        AccessibilityNode,
+    },
+    prelude::*,
+};
+
+use crate::{
+    ascii::StandardOnly,
+    ball::{Ball, Rally},
+    characters::Special,
+    four_player::{team, Lives},
+    player::{Paddle, PlayerType},
+    profiles::PlayerNames,
+    round::RoundPhase,
+    score::Score,
+    settings::Settings,
+    theme::Theme,
+};
+
+const NAME_SIZE: f32 = 24.0;
+const SCORE_SIZE: f32 = 60.0;
+const STATUS_SIZE: f32 = 18.0;
+const CENTRE_SIZE: f32 = 24.0;
+// Space between the HUD and the edges of the window, and between side by side panels
+const MARGIN: f32 = 16.0;
+
+// What each piece of HUD text shows
+#[derive(Component, Clone, Copy)]
+enum HudText {
+    Name(PlayerType),
+    Score(PlayerType),
+    // Whether the player is serving and the state of their special
+    Status(PlayerType),
+    Clock,
+    Rally,
+}
+
+// One player's name, score and status, hidden in the modes their paddle isn't in
+#[derive(Component)]
+struct PlayerPanel(PlayerType);
+
+// Seconds played in the current match, not counting the menu or the end of match summary
+#[derive(Resource, Default)]
+struct MatchClock {
+    seconds: f32,
+}
+
+// Names, scores, the server, specials, the match clock and the rally count, laid out along
+// the top of the window with bevy_ui so they stay put however the arena is scaled. Drawn in
+// the theme's font and colours, for the windowed game only.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MatchClock::default())
+            .add_systems(Startup, spawn_hud)
+            .add_systems(OnExit(RoundPhase::Menu), reset_clock)
+            .add_systems(
+                Update,
+                (tick_clock, show_panels, update_hud_text, name_scores).chain(),
+            );
+    }
+}
+
+fn column(align_items: AlignItems) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::Column,
+            align_items,
+            ..default()
+        },
+        ..default()
+    }
+}
+
+// Two panels side by side
+fn row() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            column_gap: Val::Px(MARGIN),
+            ..default()
+        },
+        ..default()
+    }
+}
+
+fn spawn_hud(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let font = asset_server.load(theme.font.as_str());
+    let text = |font_size: f32| {
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: theme.text,
+            },
+        )
+    };
+
+    // Names and scores carry their player so the theme colours them
+    let panel = |parent: &mut ChildBuilder, player: PlayerType, align_items: AlignItems| {
+        parent
+            .spawn((column(align_items), PlayerPanel(player)))
+            .with_children(|panel| {
+                panel.spawn((text(NAME_SIZE), HudText::Name(player), player));
+                panel.spawn((
+                    text(SCORE_SIZE),
+                    HudText::Score(player),
+                    player,
+                    AccessibilityNode(NodeBuilder::new(Role::StaticText)),
+                ));
+                panel.spawn((text(STATUS_SIZE), HudText::Status(player)));
+            });
+    };
+
+    // Players 1 and 3 on the left, 2 and 4 on the right, each lined up with its edge
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::SpaceBetween,
+                    align_items: AlignItems::FlexStart,
+                    padding: UiRect::all(Val::Px(MARGIN)),
+                    ..default()
+                },
+                ..default()
+            },
+            StandardOnly,
+        ))
+        .with_children(|root| {
+            root.spawn(row()).with_children(|left| {
+                panel(left, PlayerType::Player1, AlignItems::FlexStart);
+                panel(left, PlayerType::Player3, AlignItems::FlexStart);
+            });
+            root.spawn(column(AlignItems::Center))
+                .with_children(|centre| {
+                    centre.spawn((text(CENTRE_SIZE), HudText::Clock));
+                    centre.spawn((text(CENTRE_SIZE), HudText::Rally));
+                });
+            root.spawn(row()).with_children(|right| {
+                panel(right, PlayerType::Player4, AlignItems::FlexEnd);
+                panel(right, PlayerType::Player2, AlignItems::FlexEnd);
+            });
+        });
+}
+
+fn reset_clock(mut clock: ResMut<MatchClock>) {
+    clock.seconds = 0.0;
+}
+
+fn tick_clock(mut clock: ResMut<MatchClock>, phase: Res<State<RoundPhase>>, time: Res<Time>) {
+    if !matches!(phase.get(), RoundPhase::Menu | RoundPhase::MatchOver) {
+        clock.seconds += time.delta_seconds();
+    }
+}
+
+// Players 3 and 4 only have panels in the modes with four paddles
+fn show_panels(mut panel_query: Query<(&PlayerPanel, &mut Style)>, settings: Res<Settings>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (panel, mut style) in panel_query.iter_mut() {
+        let shown = match panel.0 {
+            PlayerType::Player1 | PlayerType::Player2 => true,
+            PlayerType::Player3 | PlayerType::Player4 => settings.mode.has_four_paddles(),
+        };
+        style.display = if shown { Display::Flex } else { Display::None };
+    }
+}
+
+// The score for each side in the two team modes, shown once under players 1 and 2, or
+// each player's lives in the four player modes
+fn score_label(player: PlayerType, score: &Score, lives: &Lives, settings: &Settings) -> String {
+    if settings.mode.is_four_player() {
+        lives.label(player)
+    } else if team(player) == player {
+        format!("{:02}", score.points(player))
+    } else {
+        String::new()
+    }
+}
+
+fn status_label(player: PlayerType, paddle: Option<&Paddle>, ball: Option<&Ball>) -> String {
+    let mut parts = Vec::new();
+
+    if ball.is_some_and(|ball| !ball.is_fired() && ball.owner() == player) {
+        parts.push("SERVE".to_string());
+    }
+    if let Some(paddle) = paddle {
+        if paddle.dash_seconds > 0.0 {
+            parts.push("dashing".to_string());
+        }
+        if paddle.special != Special::None && paddle.armed {
+            parts.push(format!("{} armed", paddle.special.name()));
+        } else if paddle.special != Special::None && paddle.charge >= 1.0 {
+            parts.push(format!("{} ready", paddle.special.name()));
+        }
+    }
+
+    parts.join("  ")
+}
+
+// Only writes text that has changed, so the UI isn't laid out again every frame
+fn set_text(mut text: Mut<Text>, value: String) {
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_hud_text(
+    mut text_query: Query<(&HudText, &mut Text)>,
+    paddle_query: Query<&Paddle>,
+    ball_query: Query<&Ball>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    names: Res<PlayerNames>,
+    rally: Res<Rally>,
+    clock: Res<MatchClock>,
+    settings: Res<Settings>,
+) {
+    let ball = ball_query.iter().next();
+    let seconds = clock.seconds as u32;
+
+    for (hud_text, text) in text_query.iter_mut() {
+        let value = match *hud_text {
+            HudText::Name(player) => names.name(player).to_string(),
+            HudText::Score(player) => score_label(player, &score, &lives, &settings),
+            HudText::Status(player) => {
+                let paddle = paddle_query
+                    .iter()
+                    .find(|paddle| paddle.player_type == player);
+                status_label(player, paddle, ball)
+            }
+            HudText::Clock => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+            HudText::Rally => format!("Rally {}", rally.hits),
+        };
+        set_text(text, value);
+    }
+}
+
+// Screen readers read each score out with the player's name
+fn name_scores(
+    mut score_query: Query<(&HudText, &Text, &mut AccessibilityNode), Changed<Text>>,
+    names: Res<PlayerNames>,
+) {
+    for (hud_text, text, mut node) in score_query.iter_mut() {
+        if let HudText::Score(player) = *hud_text {
+            node.set_name(format!("{} {}", names.name(player), text.sections[0].value));
+        }
+    }
+}
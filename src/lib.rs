@@ -0,0 +1,86 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+pub const TILE_SIZE: f32 = 10.0;
+
+pub mod announcements;
+pub mod arena;
+pub mod ascii;
+pub mod ascii_text;
+pub mod audio;
+pub mod ball;
+pub mod camera;
+pub mod characters;
+pub mod crt;
+pub mod effects;
+pub mod events;
+pub mod files;
+pub mod four_player;
+pub mod hud;
+pub mod menu;
+pub mod music;
+pub mod player;
+pub mod profiles;
+pub mod rng;
+pub mod round;
+pub mod score;
+pub mod settings;
+pub mod specials;
+pub mod stats;
+pub mod synth;
+pub mod theme;
+pub mod tilemap;
+pub mod tournament;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+use arena::ArenaPlugin;
+use audio::GameAudioPlugin;
+use ball::BallPlugin;
+use characters::CharactersPlugin;
+use events::GameEventsPlugin;
+use four_player::FourPlayerPlugin;
+use menu::MenuPlugin;
+use player::PlayerPlugin;
+use profiles::ProfilesPlugin;
+use round::RoundPlugin;
+use score::ScorePlugin;
+use settings::{Settings, SettingsPlugin};
+use specials::SpecialsPlugin;
+use stats::StatsPlugin;
+use theme::ThemePlugin;
+use tournament::TournamentPlugin;
+
+// The gameplay simulation, shared by the windowed game and the terminal renderer
+pub struct GamePlugins {
+    pub settings: Settings,
+}
+
+impl PluginGroup for GamePlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(ArenaPlugin {
+                arena: self.settings.starting_map.clone(),
+            })
+            .add(ThemePlugin {
+                theme: self.settings.theme.clone(),
+            })
+            .add(GameEventsPlugin)
+            .add(PlayerPlugin)
+            .add(CharactersPlugin)
+            .add(SpecialsPlugin)
+            .add(BallPlugin)
+            .add(RoundPlugin)
+            .add(ScorePlugin {
+                points_to_win: self.settings.points_to_win,
+            })
+            .add(FourPlayerPlugin)
+            .add(StatsPlugin)
+            .add(ProfilesPlugin)
+            .add(TournamentPlugin)
+            .add(MenuPlugin)
+            .add(GameAudioPlugin)
+            .add(SettingsPlugin {
+                settings: self.settings,
+            })
+    }
+}
@@ -1,44 +1,60 @@
-use bevy::prelude::*;
+use std::time::Duration;
 
-const ARENA_WIDTH: f32 = 800.0;
-const ARENA_HEIGHT: f32 = 600.0;
+use bevy::{asset::ChangeWatcher, prelude::*, window::WindowMode};
 
-mod ball;
-mod player;
-mod tilemap;
-mod score;
-mod audio;
-
-use ball::BallPlugin;
-use player::PlayerPlugin;
-use tilemap::TilemapPlugin;
-use score::ScorePlugin;
-use audio::GameAudioPlugin;
+use game_tut::{
+    announcements::AnnouncementsPlugin,
+    ascii::AsciiPlugin,
+    ascii_text::AsciiTextPlugin,
+    camera::CameraPlugin,
+    crt::CrtPlugin,
+    effects::EffectsPlugin,
+    hud::HudPlugin,
+    music::MusicPlugin,
+    settings::load_settings_or_exit,
+    tilemap::TilemapPlugin,
+    GamePlugins,
+};
 
 fn main() {
+    let settings = load_settings_or_exit();
+
     App::new()
         .add_plugins(
             DefaultPlugins
                 .set(ImagePlugin::default_nearest())
+                // Reloads assets when their files are saved, for editing themes while playing
+                .set(AssetPlugin {
+                    watch_for_changes: ChangeWatcher::with_delay(Duration::from_millis(200)),
+                    ..default()
+                })
                 .set(WindowPlugin {
                     primary_window: Some(Window {
                         title: "Space puzzle game".into(),
-                        resolution: (1000.0, 1000.0).into(),
-                        resizable: false,
+                        resolution: (settings.window_width, settings.window_height).into(),
+                        mode: if settings.fullscreen {
+                            WindowMode::BorderlessFullscreen
+                        } else {
+                            WindowMode::Windowed
+                        },
+                        resizable: true,
 
                         ..default()
                     }),
                     ..default()
                 }),
         )
-        // .add_systems(PreStartup, load_ascii)
-        .add_systems(Startup, spawn_camera)
-        .add_plugins((PlayerPlugin, TilemapPlugin, BallPlugin, ScorePlugin, GameAudioPlugin))
+        .add_plugins(GamePlugins { settings })
+        .add_plugins((
+            CameraPlugin,
+            CrtPlugin,
+            AnnouncementsPlugin,
+            EffectsPlugin,
+            HudPlugin,
+            TilemapPlugin,
+            MusicPlugin,
+            AsciiPlugin,
+            AsciiTextPlugin,
+        ))
         .run();
 }
-
-fn spawn_camera(mut commands: Commands) {
-    let mut camera = Camera2dBundle::default();
-
-    commands.spawn(camera);
-}
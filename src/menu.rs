@@ -0,0 +1,323 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::{
+    ascii::StandardOnly,
+    characters::{CharacterChoices, Characters},
+    player::PlayerType,
+    profiles::{PlayerNames, Profiles},
+    round::RoundPhase,
+    score::Score,
+    settings::{GameMode, Settings},
+    theme::Theme,
+    tournament::Tournament,
+};
+
+const LEADERBOARD_SIZE: usize = 10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum MenuScreen {
+    #[default]
+    Main,
+    Leaderboard,
+    Bracket,
+    Characters,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum MenuItem {
+    Play,
+    Characters,
+    // Picks the next character for this player
+    Character(PlayerType),
+    Tournament,
+    Leaderboard,
+    Quit,
+    NextMatch,
+    Back,
+}
+
+#[derive(Resource, Default)]
+pub struct Menu {
+    pub screen: MenuScreen,
+    selected: usize,
+}
+
+// What the menu currently shows, as lines of plain text so every renderer can draw it.
+// Empty while a match is being played.
+#[derive(Resource, Default)]
+pub struct MenuText {
+    pub text: String,
+    // Label of the selected item, for screen readers
+    pub focused: String,
+}
+
+#[derive(Component)]
+struct MenuTextDisplay;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Menu::default())
+            .insert_resource(MenuText::default())
+            .add_systems(Startup, spawn_menu_text)
+            .add_systems(OnEnter(RoundPhase::Menu), open_menu)
+            .add_systems(OnExit(RoundPhase::Menu), close_menu)
+            .add_systems(
+                Update,
+                (
+                    navigate_menu.run_if(in_state(RoundPhase::Menu)),
+                    write_menu_text.run_if(in_state(RoundPhase::Menu)),
+                    update_menu_text_display,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Straight after a tournament match the bracket is shown, so the players see who goes through
+// and can start the next match
+fn open_menu(mut menu: ResMut<Menu>, tournament: Option<ResMut<Tournament>>) {
+    *menu = Menu::default();
+
+    if tournament.is_some_and(|mut tournament| tournament.take_finished()) {
+        menu.screen = MenuScreen::Bracket;
+    }
+}
+
+fn close_menu(mut menu_text: ResMut<MenuText>) {
+    menu_text.text.clear();
+    menu_text.focused.clear();
+}
+
+// The players with a paddle in the chosen mode
+fn players_in_mode(settings: &Settings) -> &'static [PlayerType] {
+    if settings.mode.has_four_paddles() {
+        &PlayerType::ALL
+    } else {
+        &[PlayerType::Player1, PlayerType::Player2]
+    }
+}
+
+// The items that can be chosen on each screen
+fn menu_items(
+    screen: MenuScreen,
+    tournament: Option<&Tournament>,
+    settings: &Settings,
+) -> Vec<MenuItem> {
+    match screen {
+        MenuScreen::Main if tournament.is_some() => vec![
+            MenuItem::Play,
+            MenuItem::Characters,
+            MenuItem::Tournament,
+            MenuItem::Leaderboard,
+            MenuItem::Quit,
+        ],
+        MenuScreen::Main => vec![
+            MenuItem::Play,
+            MenuItem::Characters,
+            MenuItem::Leaderboard,
+            MenuItem::Quit,
+        ],
+        MenuScreen::Characters => players_in_mode(settings)
+            .iter()
+            .map(|player| MenuItem::Character(*player))
+            .chain([MenuItem::Back])
+            .collect(),
+        MenuScreen::Leaderboard => vec![MenuItem::Back],
+        MenuScreen::Bracket if tournament.and_then(Tournament::next_match).is_some() => {
+            vec![MenuItem::NextMatch, MenuItem::Back]
+        }
+        MenuScreen::Bracket => vec![MenuItem::Back],
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    keyboard: Res<Input<KeyCode>>,
+    mut menu: ResMut<Menu>,
+    mut score: ResMut<Score>,
+    mut names: ResMut<PlayerNames>,
+    mut tournament: Option<ResMut<Tournament>>,
+    mut next_phase: ResMut<NextState<RoundPhase>>,
+    mut exit: EventWriter<AppExit>,
+    mut choices: ResMut<CharacterChoices>,
+    characters: Res<Characters>,
+    settings: Res<Settings>,
+) {
+    let items = menu_items(menu.screen, tournament.as_deref(), &settings);
+
+    if keyboard.any_just_pressed([KeyCode::W, KeyCode::Up]) {
+        menu.selected = (menu.selected + items.len() - 1) % items.len();
+    }
+    if keyboard.any_just_pressed([KeyCode::S, KeyCode::Down]) {
+        menu.selected = (menu.selected + 1) % items.len();
+    }
+    if !keyboard.any_just_pressed([KeyCode::Space, KeyCode::Return]) {
+        return;
+    }
+
+    match items[menu.selected.min(items.len() - 1)] {
+        MenuItem::Play => {
+            *names = PlayerNames::from_settings(&settings);
+            score.reset();
+            next_phase.set(RoundPhase::Countdown);
+        }
+        MenuItem::Characters => show_screen(&mut menu, MenuScreen::Characters),
+        MenuItem::Character(player) => choices.cycle(player, characters.0.len()),
+        MenuItem::Tournament => show_screen(&mut menu, MenuScreen::Bracket),
+        MenuItem::Leaderboard => show_screen(&mut menu, MenuScreen::Leaderboard),
+        MenuItem::Quit => exit.send(AppExit),
+        MenuItem::NextMatch => {
+            let Some(tournament) = tournament.as_mut() else {
+                return;
+            };
+            let Some(players) = tournament
+                .next_match()
+                .and_then(|index| tournament.start_match(index))
+            else {
+                return;
+            };
+
+            *names = players;
+            score.reset();
+            next_phase.set(RoundPhase::Countdown);
+        }
+        MenuItem::Back => show_screen(&mut menu, MenuScreen::Main),
+    }
+}
+
+fn show_screen(menu: &mut Menu, screen: MenuScreen) {
+    menu.screen = screen;
+    menu.selected = 0;
+}
+
+fn write_menu_text(
+    menu: Res<Menu>,
+    profiles: Res<Profiles>,
+    tournament: Option<Res<Tournament>>,
+    characters: Res<Characters>,
+    choices: Res<CharacterChoices>,
+    settings: Res<Settings>,
+    mut menu_text: ResMut<MenuText>,
+) {
+    if !menu.is_changed()
+        && !profiles.is_changed()
+        && !choices.is_changed()
+        && !menu_text.text.is_empty()
+    {
+        return;
+    }
+
+    let names = PlayerNames::from_settings(&settings);
+    let tournament = tournament.as_deref();
+
+    let text = match menu.screen {
+        MenuScreen::Main => "PONG".to_string(),
+        MenuScreen::Leaderboard => leaderboard_text(&profiles, &names),
+        MenuScreen::Bracket => tournament.map(Tournament::bracket_text).unwrap_or_default(),
+        MenuScreen::Characters => "CHARACTERS".to_string(),
+    };
+
+    let items = menu_items(menu.screen, tournament, &settings);
+    let mut lines = vec![text, String::new()];
+    for (index, item) in items.iter().enumerate() {
+        let label = match item {
+            MenuItem::Play => match settings.mode {
+                GameMode::Classic => format!("Play  {} vs {}", names.player1, names.player2),
+                GameMode::Doubles => "Play  doubles".to_string(),
+                GameMode::FreeForAll => "Play  four player free for all".to_string(),
+                GameMode::Teams => "Play  two against two".to_string(),
+            },
+            MenuItem::Characters => "Characters".to_string(),
+            MenuItem::Character(player) => {
+                let character = characters.get(choices.get(*player));
+                format!(
+                    "Player {}  {:<10}{}",
+                    player.number(),
+                    character.name,
+                    character.description()
+                )
+            }
+            MenuItem::Tournament => "Tournament".to_string(),
+            MenuItem::Leaderboard => "Leaderboard".to_string(),
+            MenuItem::Quit => "Quit".to_string(),
+            MenuItem::NextMatch => "Play next match".to_string(),
+            MenuItem::Back => "Back".to_string(),
+        };
+        let cursor = if index == menu.selected { '>' } else { ' ' };
+        if index == menu.selected {
+            menu_text.focused = label.clone();
+        }
+
+        lines.push(format!("{cursor} {label}"));
+    }
+    lines.push(String::new());
+    lines.push("W/S or Up/Down to choose, Space to select".to_string());
+
+    menu_text.text = lines.join("\n");
+}
+
+fn leaderboard_text(profiles: &Profiles, names: &PlayerNames) -> String {
+    let mut lines = vec![
+        "LEADERBOARD".to_string(),
+        String::new(),
+        format!("{:>2}  {:<18}{:>6}{:>4}{:>4}", "#", "Name", "Elo", "W", "L"),
+    ];
+
+    let leaderboard = profiles.leaderboard();
+    if leaderboard.is_empty() {
+        lines.push("No matches played yet".to_string());
+    }
+
+    for (rank, (name, profile)) in leaderboard.iter().take(LEADERBOARD_SIZE).enumerate() {
+        let name: String = name.chars().take(17).collect();
+        lines.push(format!(
+            "{:>2}  {:<18}{:>6.0}{:>4}{:>4}",
+            rank + 1,
+            name,
+            profile.rating,
+            profile.wins,
+            profile.losses
+        ));
+    }
+
+    let record = profiles.head_to_head(&names.player1, &names.player2);
+    lines.push(String::new());
+    lines.push(format!(
+        "{} vs {}: {} - {}",
+        names.player1, names.player2, record.wins, record.losses
+    ));
+    lines.join("\n")
+}
+
+fn spawn_menu_text(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let text_style = TextStyle {
+        font: asset_server.load(theme.font.as_str()),
+        font_size: 28.0,
+        color: theme.text,
+    };
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", text_style).with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+            ..default()
+        },
+        MenuTextDisplay,
+        StandardOnly,
+    ));
+}
+
+fn update_menu_text_display(
+    menu_text: Res<MenuText>,
+    mut text_query: Query<&mut Text, With<MenuTextDisplay>>,
+) {
+    if !menu_text.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = menu_text.text.clone();
+    }
+}
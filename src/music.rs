@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_kira_audio::{prelude::*, AudioSource};
+
+use crate::{
+    ball::{Ball, Rally, INITIAL_BALL_SPEED},
+    events::GoalScored,
+    four_player::Lives,
+    round::RoundPhase,
+    score::Score,
+    settings::Settings,
+    synth::{render, render_pattern, Envelope, SynthSound, Waveform},
+};
+
+// Every stem is generated by the synth as the same two bar loop at 120bpm, so they stay in
+// sync while looping together
+const BEAT_SECONDS: f32 = 0.5;
+const LOOP_BEATS: usize = 8;
+// Root of each two beat chord, A minor, F, C and G
+const CHORD_ROOTS: [f32; 4] = [110.0, 87.31, 130.81, 98.0];
+// Semitone steps above the root of the arpeggio, root, fifth and octave so it fits both the
+// major and minor chords
+const ARPEGGIO_STEPS: [f32; 4] = [0.0, 7.0, 12.0, 7.0];
+
+const CROSSFADE_SECONDS: f32 = 1.5;
+// Hits in a rally, or extra ball speed, needed to bring in the next layer
+const RALLY_HITS_PER_LEVEL: u32 = 4;
+const SPEED_PER_LEVEL: f32 = 250.0;
+const MAX_INTENSITY: u32 = 2;
+
+#[derive(Resource)]
+struct BaseLayer;
+
+#[derive(Resource)]
+struct DriveLayer;
+
+#[derive(Resource)]
+struct IntenseLayer;
+
+#[derive(Resource)]
+struct TensionLayer;
+
+#[derive(Resource)]
+struct StingerChannel;
+
+#[derive(Resource)]
+struct MusicStems {
+    base: Handle<AudioSource>,
+    drive: Handle<AudioSource>,
+    intense: Handle<AudioSource>,
+    tension: Handle<AudioSource>,
+    stinger: Handle<AudioSource>,
+}
+
+// What the music is currently doing, so layers are only faded when something changes
+#[derive(Resource, Default)]
+struct MusicDirector {
+    intensity: u32,
+    tension: bool,
+}
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_channel::<BaseLayer>()
+            .add_audio_channel::<DriveLayer>()
+            .add_audio_channel::<IntenseLayer>()
+            .add_audio_channel::<TensionLayer>()
+            .add_audio_channel::<StingerChannel>()
+            .insert_resource(MusicDirector::default())
+            .add_systems(Startup, (generate_stems, start_music).chain())
+            .add_systems(Update, (update_music_layers, play_goal_stinger));
+    }
+}
+
+fn note(waveform: Waveform, frequency: f32, duration: f32, volume: f32) -> SynthSound {
+    SynthSound {
+        waveform,
+        frequency,
+        frequency_end: None,
+        duty: 0.25,
+        duration,
+        envelope: Envelope {
+            attack: 0.005,
+            decay: 0.05,
+            sustain: 0.7,
+            release: 0.05,
+        },
+        volume,
+    }
+}
+
+fn chord_root(beat: usize) -> f32 {
+    CHORD_ROOTS[beat / 2 % CHORD_ROOTS.len()]
+}
+
+fn semitones(frequency: f32, steps: f32) -> f32 {
+    frequency * 2.0_f32.powf(steps / 12.0)
+}
+
+// The base layer is a bass line, drive adds hi-hats on every half beat, intense an arpeggio
+// on every quarter beat and tension a high pulse over the top
+fn generate_stems(mut commands: Commands, mut audio_sources: ResMut<Assets<AudioSource>>) {
+    // The goal stinger is a single rising sweep
+    let stinger = audio_sources.add(AudioSource {
+        sound: render(&SynthSound {
+            frequency_end: Some(880.0),
+            ..note(Waveform::Square, 220.0, 0.4, 0.3)
+        }),
+    });
+
+    let length = BEAT_SECONDS * LOOP_BEATS as f32;
+    let mut pattern = |notes: Vec<(f32, SynthSound)>| {
+        audio_sources.add(AudioSource {
+            sound: render_pattern(&notes, length),
+        })
+    };
+
+    let base = (0..LOOP_BEATS)
+        .map(|beat| {
+            let sound = note(
+                Waveform::Triangle,
+                chord_root(beat),
+                BEAT_SECONDS * 0.8,
+                0.5,
+            );
+            (beat as f32 * BEAT_SECONDS, sound)
+        })
+        .collect();
+    let drive = (0..LOOP_BEATS * 2)
+        .map(|step| {
+            let mut sound = note(Waveform::Noise, 8000.0, 0.02, 0.15);
+            sound.envelope.sustain = 0.3;
+            (step as f32 * BEAT_SECONDS / 2.0, sound)
+        })
+        .collect();
+    let intense = (0..LOOP_BEATS * 4)
+        .map(|step| {
+            let root = chord_root(step / 4) * 4.0;
+            let frequency = semitones(root, ARPEGGIO_STEPS[step % ARPEGGIO_STEPS.len()]);
+            let sound = note(Waveform::Square, frequency, BEAT_SECONDS / 8.0, 0.12);
+            (step as f32 * BEAT_SECONDS / 4.0, sound)
+        })
+        .collect();
+    let tension = (0..LOOP_BEATS * 2)
+        .map(|step| {
+            let frequency = if step % 2 == 0 { 659.25 } else { 698.46 };
+            let sound = note(Waveform::Square, frequency, BEAT_SECONDS / 4.0, 0.08);
+            (step as f32 * BEAT_SECONDS / 2.0, sound)
+        })
+        .collect();
+
+    commands.insert_resource(MusicStems {
+        base: pattern(base),
+        drive: pattern(drive),
+        intense: pattern(intense),
+        tension: pattern(tension),
+        stinger,
+    });
+}
+
+fn start_music(
+    stems: Res<MusicStems>,
+    settings: Res<Settings>,
+    base: Res<AudioChannel<BaseLayer>>,
+    drive: Res<AudioChannel<DriveLayer>>,
+    intense: Res<AudioChannel<IntenseLayer>>,
+    tension: Res<AudioChannel<TensionLayer>>,
+    stinger: Res<AudioChannel<StingerChannel>>,
+) {
+    base.play(stems.base.clone()).looped();
+    base.set_volume(settings.volume);
+    stinger.set_volume(settings.volume);
+
+    // The other layers play silently from the start and are faded in as the game heats up
+    drive.play(stems.drive.clone()).looped();
+    drive.set_volume(0.0);
+    intense.play(stems.intense.clone()).looped();
+    intense.set_volume(0.0);
+    tension.play(stems.tension.clone()).looped();
+    tension.set_volume(0.0);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_music_layers(
+    mut director: ResMut<MusicDirector>,
+    rally: Res<Rally>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    phase: Res<State<RoundPhase>>,
+    settings: Res<Settings>,
+    ball_query: Query<&Ball>,
+    drive: Res<AudioChannel<DriveLayer>>,
+    intense: Res<AudioChannel<IntenseLayer>>,
+    tension: Res<AudioChannel<TensionLayer>>,
+) {
+    let speed = ball_query
+        .iter()
+        .map(|ball| ball.speed())
+        .fold(INITIAL_BALL_SPEED, f32::max);
+
+    let rally_intensity = rally.hits / RALLY_HITS_PER_LEVEL;
+    let speed_intensity = ((speed - INITIAL_BALL_SPEED) / SPEED_PER_LEVEL) as u32;
+    let intensity = rally_intensity.max(speed_intensity).min(MAX_INTENSITY);
+
+    if intensity != director.intensity {
+        crossfade(&*drive, intensity >= 1, settings.volume);
+        crossfade(&*intense, intensity >= 2, settings.volume);
+        director.intensity = intensity;
+    }
+
+    // The four player modes are played for lives, the score isn't kept in them. Either stays
+    // at match point once the match is won, until the next one starts.
+    let match_over = matches!(phase.get(), RoundPhase::Menu | RoundPhase::MatchOver);
+    let match_point = if settings.mode.is_four_player() {
+        lives.is_match_point(settings.mode)
+    } else {
+        score.is_match_point()
+    };
+    let match_point = match_point && !match_over;
+
+    if match_point != director.tension {
+        crossfade(&*tension, match_point, settings.volume);
+        director.tension = match_point;
+    }
+}
+
+fn crossfade(channel: &impl AudioControl, on: bool, volume: f64) {
+    let volume = if on { volume } else { 0.0 };
+
+    channel
+        .set_volume(volume)
+        .linear_fade_in(Duration::from_secs_f32(CROSSFADE_SECONDS));
+}
+
+fn play_goal_stinger(
+    mut goal_events: EventReader<GoalScored>,
+    stems: Res<MusicStems>,
+    stinger: Res<AudioChannel<StingerChannel>>,
+) {
+    if !goal_events.is_empty() {
+        goal_events.clear();
+        stinger.play(stems.stinger.clone());
+    }
+}
@@ -1,114 +1,241 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    ascii::{AsciiArt, GLYPH_SOLID},
+    arena::ArenaConfig,
     ball::{fire_ball, Ball},
-    ARENA_HEIGHT,
+    characters::Special,
+    round::paddles_can_move,
+    settings::{AiDifficulty, GameMode, PaddlePhysics, Settings},
 };
 
 pub struct PlayerPlugin;
 
 const PLAYER_SPEED: f32 = 300.0;
-pub const PADDLE_HEIGHT: f32 = 60.0;
+// Longest gap between two presses of the same key that counts as a double tap
+const DOUBLE_TAP_SECONDS: f32 = 0.25;
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum PlayerType {
+    // Left
     Player1,
+    // Right
     Player2,
+    // Top in the four player modes, or forward on the left in doubles
+    Player3,
+    // Bottom in the four player modes, or forward on the right in doubles
+    Player4,
+}
+
+impl PlayerType {
+    pub const ALL: [PlayerType; 4] = [
+        PlayerType::Player1,
+        PlayerType::Player2,
+        PlayerType::Player3,
+        PlayerType::Player4,
+    ];
+
+    pub fn number(self) -> u32 {
+        match self {
+            PlayerType::Player1 => 1,
+            PlayerType::Player2 => 2,
+            PlayerType::Player3 => 3,
+            PlayerType::Player4 => 4,
+        }
+    }
+
+    // The rest are about the side of the arena whose goal is this player's. Paddles guarding
+    // the top and bottom lie flat and move left and right.
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, PlayerType::Player3 | PlayerType::Player4)
+    }
+
+    // Direction from this player's goal into the arena, which the ball leaves their paddle in
+    pub fn inward(self) -> Vec3 {
+        match self {
+            PlayerType::Player1 => Vec3::X,
+            PlayerType::Player2 => Vec3::NEG_X,
+            PlayerType::Player3 => Vec3::NEG_Y,
+            PlayerType::Player4 => Vec3::Y,
+        }
+    }
+
+    // Direction the paddle moves in when its "up" key is held
+    pub fn track(self) -> Vec3 {
+        if self.is_horizontal() {
+            Vec3::X
+        } else {
+            Vec3::Y
+        }
+    }
+}
+
+// How a paddle picks up and loses speed with momentum paddle physics, set by its character.
+// The paddle's speed is its top speed.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct Handling {
+    // Units per second the paddle speeds up by each second while a key is held
+    pub acceleration: f32,
+    // Units per second the paddle slows down by each second once the keys are let go
+    pub friction: f32,
+    // How much faster than its top speed a paddle moves while dashing
+    pub dash_factor: f32,
+    // How long a double tap dash lasts
+    pub dash_seconds: f32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            acceleration: 2400.0,
+            friction: 1800.0,
+            dash_factor: 2.5,
+            dash_seconds: 0.15,
+        }
+    }
+}
+
+impl Handling {
+    // A paddle that never speeds up or slows down, or that dashes slower than it moves,
+    // can't be played
+    pub fn check(&self) -> Result<(), String> {
+        if self.acceleration <= 0.0 {
+            return Err(format!(
+                "acceleration must be more than 0, got {}",
+                self.acceleration
+            ));
+        }
+        if self.friction <= 0.0 {
+            return Err(format!(
+                "friction must be more than 0, got {}",
+                self.friction
+            ));
+        }
+        if self.dash_factor < 1.0 {
+            return Err(format!(
+                "dash_factor must be at least 1, got {}",
+                self.dash_factor
+            ));
+        }
+        if self.dash_seconds < 0.0 {
+            return Err(format!(
+                "dash_seconds must not be negative, got {}",
+                self.dash_seconds
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// How far forward of its goal a paddle plays. Only doubles has forward paddles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Lane {
+    #[default]
+    Back,
+    Forward,
 }
 
 #[derive(Component)]
 pub struct Paddle {
     pub speed: f32,
     pub player_type: PlayerType,
-    pub y_velocity: f32,
+    // The player whose goal this paddle guards, and so its team in doubles, where players 3
+    // and 4 partner players 1 and 2. In the other modes it's the paddle's own player.
+    pub team: PlayerType,
+    pub lane: Lane,
+    // Speed along the paddle's track, positive when moving up or right
+    pub velocity: f32,
+    // Size along the paddle's track
+    pub length: f32,
+    // Multiplies how much each return speeds the ball up
+    pub hit_power: f32,
+    pub special: Special,
+    // Special meter, filled by returning the ball and spent when it reaches 1.0
+    pub charge: f32,
+    // Whether the special is used on the paddle's next return
+    pub armed: bool,
+    // Seconds left of a dash, the paddle moves faster until then
+    pub dash_seconds: f32,
+    pub handling: Handling,
+    // Direction of the last key press along the track and the seconds since, for spotting
+    // double taps
+    pub tap_direction: f32,
+    pub tap_seconds: f32,
+}
+
+impl Paddle {
+    pub fn size(&self, arena: &ArenaConfig) -> Vec2 {
+        arena.paddle_size(self.team, self.length)
+    }
 }
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, spawn_players)
             // .add_systems(Update, camera_follow.after(player_movement))
-            .add_systems(Update, (player_movement));
+            .add_systems(
+                Update,
+                player_movement.run_if(paddles_can_move),
+            );
     }
 }
 
-fn spawn_players(mut commands: Commands) {
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::WHITE,
-                custom_size: Some(Vec2::new(10.0, PADDLE_HEIGHT)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(-300.0, 0.0, 0.0)),
-            ..Default::default()
-        },
-        Paddle {
-            speed: PLAYER_SPEED,
-            player_type: PlayerType::Player1,
-            y_velocity: 0.0,
-        },
-    ));
+// The top and bottom paddles are spawned by `FourPlayerPlugin` when a match starts. Each
+// paddle's character is applied by `CharactersPlugin` once it's spawned, and its colours by
+// `ThemePlugin`.
+fn spawn_players(mut commands: Commands, arena: Res<ArenaConfig>, settings: Res<Settings>) {
+    let mut paddles = vec![
+        (PlayerType::Player1, PlayerType::Player1, Lane::Back),
+        (PlayerType::Player2, PlayerType::Player2, Lane::Back),
+    ];
+    if settings.mode == GameMode::Doubles {
+        paddles.push((PlayerType::Player3, PlayerType::Player1, Lane::Forward));
+        paddles.push((PlayerType::Player4, PlayerType::Player2, Lane::Forward));
+    }
 
+    for (player, team, lane) in paddles {
+        spawn_paddle(&mut commands, &arena, player, team, lane);
+    }
+}
+
+pub fn spawn_paddle(
+    commands: &mut Commands,
+    arena: &ArenaConfig,
+    player: PlayerType,
+    team: PlayerType,
+    lane: Lane,
+) {
     commands.spawn((
         SpriteBundle {
             sprite: Sprite {
                 color: Color::WHITE,
-                custom_size: Some(Vec2::new(10.0, PADDLE_HEIGHT)),
+                custom_size: Some(arena.paddle_size(team, arena.paddle_height)),
                 ..Default::default()
             },
-            transform: Transform::from_translation(Vec3::new(300.0, 0.0, 0.0)),
+            transform: Transform::from_translation(arena.paddle_position(team, lane)),
             ..Default::default()
         },
         Paddle {
             speed: PLAYER_SPEED,
-            player_type: PlayerType::Player2,
-            y_velocity: 0.0,
+            player_type: player,
+            team,
+            lane,
+            velocity: 0.0,
+            length: arena.paddle_height,
+            hit_power: 1.0,
+            special: Special::None,
+            charge: 0.0,
+            armed: false,
+            dash_seconds: 0.0,
+            handling: Handling::default(),
+            tap_direction: 0.0,
+            tap_seconds: f32::MAX,
         },
+        AsciiArt { glyph: GLYPH_SOLID },
     ));
-    // commands.spawn((
-    //     SpriteBundle {
-    //         sprite: Sprite {
-    //             custom_size: Some(Vec2::new(32.0, 32.0)),
-    //             ..default()
-    //         },
-    //         texture: character_handle.clone(),
-    //         transform: Transform {
-    //             translation: Vec3 {
-    //                 x: 2 as f32 * TILE_SIZE,
-    //                 y: -3 as f32 * TILE_SIZE,
-    //                 z: 2.0,
-    //             },
-    //             ..default()
-    //         },
-    //         ..default()
-    //     },
-    //     Player {
-    //         speed: 6.0,
-    //         grounded: true,
-    //         y_velocity: -1.0,
-    //         jump_strength: 6.0,
-    //     },
-    // ));
-
-    // commands.spawn((
-    //     SpriteBundle {
-    //         sprite: Sprite {
-    //             custom_size: Some(Vec2::new(64.0, 64.0)),
-    //             ..default()
-    //         },
-    //         texture: enemy_handle.clone(),
-    //         transform: Transform {
-    //             translation: Vec3 {
-    //                 x: 14 as f32 * TILE_SIZE,
-    //                 y: -8 as f32 * TILE_SIZE,
-    //                 z: 2.0,
-    //             },
-    //             ..default()
-    //         },
-    //         ..default()
-    //     },
-
-    // ));
 }
 
 // fn wall_collision_check(
@@ -132,44 +259,170 @@ fn player_movement(
     mut players_query: Query<(&mut Paddle, &mut Transform)>,
     mut ball_query: Query<(&mut Ball, &mut Transform), Without<Paddle>>,
     keyboard: Res<Input<KeyCode>>,
+    arena: Res<ArenaConfig>,
+    settings: Res<Settings>,
     time: Res<Time>,
 ) {
+    let delta_seconds = time.delta_seconds();
+    if delta_seconds == 0.0 {
+        return;
+    }
+
     for (mut paddle, mut transform) in players_query.iter_mut() {
-        let mut y_delta = 0.0;
+        let mut delta = 0.0;
+        // Which way the player wants the paddle to go, for momentum physics
+        let mut direction: f32 = 0.0;
 
-        let (mut ball, mut _ball_transform) = ball_query.single_mut();
+        // There is no ball between a goal and the next serve
+        let mut ball = ball_query.get_single_mut().ok();
 
         if keyboard.pressed(KeyCode::Space) {
-            fire_ball(&mut *ball);
+            if let Some((ball, _)) = &mut ball {
+                fire_ball(ball);
+            }
         }
 
+        // Keys that move each paddle up or right, and down or left
+        let (forward, back) = match (paddle.player_type, paddle.team.is_horizontal()) {
+            (PlayerType::Player1, _) => (KeyCode::W, KeyCode::S),
+            (PlayerType::Player2, _) => (KeyCode::Up, KeyCode::Down),
+            (PlayerType::Player3, true) => (KeyCode::L, KeyCode::J),
+            (PlayerType::Player3, false) => (KeyCode::I, KeyCode::K),
+            (PlayerType::Player4, true) => (KeyCode::Right, KeyCode::Left),
+            (PlayerType::Player4, false) => (KeyCode::PageUp, KeyCode::PageDown),
+        };
+
         match paddle.player_type {
-            PlayerType::Player1 => {
-                if keyboard.pressed(KeyCode::W) {
-                    y_delta += paddle.speed * time.delta_seconds();
-                }
-                if keyboard.pressed(KeyCode::S) {
-                    y_delta -= paddle.speed * time.delta_seconds();
+            // The computer plays every paddle but the left one
+            PlayerType::Player2 | PlayerType::Player3 | PlayerType::Player4
+                if settings.ai_difficulty != AiDifficulty::Off =>
+            {
+                delta = ai_movement(
+                    settings.ai_difficulty,
+                    &paddle,
+                    &transform,
+                    ball.as_ref()
+                        .map(|(ball, ball_transform)| (&**ball, &**ball_transform)),
+                    delta_seconds,
+                );
+                // Easier computers still only ask for part of the paddle's speed
+                direction = delta / (paddle.speed * delta_seconds);
+
+                // The computer serves as soon as it has lined up in the middle
+                if let Some((ball, _)) = &mut ball {
+                    if !ball.is_fired() && ball.owner() == paddle.player_type && delta == 0.0 {
+                        fire_ball(ball);
+                    }
                 }
             }
-            PlayerType::Player2 => {
-                if keyboard.pressed(KeyCode::Up) {
-                    y_delta += paddle.speed * time.delta_seconds();
+            _ => {
+                if keyboard.pressed(forward) {
+                    delta += paddle.speed * delta_seconds;
+                    direction += 1.0;
                 }
-                if keyboard.pressed(KeyCode::Down) {
-                    y_delta -= paddle.speed * time.delta_seconds();
+                if keyboard.pressed(back) {
+                    delta -= paddle.speed * delta_seconds;
+                    direction -= 1.0;
+                }
+
+                // Pressing the same direction twice in quick succession dashes
+                paddle.tap_seconds += delta_seconds;
+                for (key, tap_direction) in [(forward, 1.0), (back, -1.0)] {
+                    if !keyboard.just_pressed(key) {
+                        continue;
+                    }
+                    if settings.paddle_physics == PaddlePhysics::Momentum
+                        && paddle.tap_direction == tap_direction
+                        && paddle.tap_seconds < DOUBLE_TAP_SECONDS
+                    {
+                        paddle.dash_seconds = paddle.handling.dash_seconds;
+                        paddle.velocity =
+                            tap_direction * paddle.speed * paddle.handling.dash_factor;
+                    }
+                    paddle.tap_direction = tap_direction;
+                    paddle.tap_seconds = 0.0;
                 }
             }
         }
-        // Calculate the new y position
-        let new_y = transform.translation.y + y_delta;
 
-        // Clamp the y position to be within the arena bounds
-        transform.translation.y = new_y.clamp(
-            -ARENA_HEIGHT / 2.0 + PADDLE_HEIGHT / 2.0,
-            ARENA_HEIGHT / 2.0 - PADDLE_HEIGHT / 2.0,
-        );
+        let dash_factor = if paddle.dash_seconds > 0.0 {
+            paddle.dash_seconds -= delta_seconds;
+            paddle.handling.dash_factor
+        } else {
+            1.0
+        };
+
+        if settings.paddle_physics == PaddlePhysics::Momentum {
+            delta = momentum_movement(&mut paddle, direction, dash_factor, delta_seconds);
+        } else {
+            delta *= dash_factor;
+        }
+
+        // Move along the track, clamped to be within the arena bounds
+        let track = paddle.team.track();
+        let limit = arena.track_limit(paddle.team, paddle.length);
+        let position = (transform.translation.dot(track) + delta).clamp(-limit, limit);
+        let moved = position - transform.translation.dot(track);
+        transform.translation += track * moved;
+
+        // Running into the end of the track stops the paddle
+        paddle.velocity = moved / delta_seconds;
+    }
+}
+
+// Speeds the paddle up towards its top speed in the direction wanted, or lets friction slow
+// it down when no direction is wanted. Returns how far it moves this frame.
+fn momentum_movement(
+    paddle: &mut Paddle,
+    direction: f32,
+    dash_factor: f32,
+    delta_seconds: f32,
+) -> f32 {
+    let handling = paddle.handling;
+    let target = direction * paddle.speed * dash_factor;
+    let rate = if direction == 0.0 {
+        handling.friction
+    } else {
+        handling.acceleration
+    };
+
+    let change = target - paddle.velocity;
+    paddle.velocity += change.clamp(-rate * delta_seconds, rate * delta_seconds);
+
+    paddle.velocity * delta_seconds
+}
 
-        paddle.y_velocity = y_delta / time.delta_seconds();
+// Moves the computer's paddle along its track towards the ball while it's coming towards
+// it, and back to the middle otherwise. Easier difficulties move slower and settle further
+// from the ball.
+fn ai_movement(
+    difficulty: AiDifficulty,
+    paddle: &Paddle,
+    transform: &Transform,
+    ball: Option<(&Ball, &Transform)>,
+    delta_seconds: f32,
+) -> f32 {
+    let (speed_factor, dead_zone) = match difficulty {
+        AiDifficulty::Off => return 0.0,
+        AiDifficulty::Easy => (0.45, 25.0),
+        AiDifficulty::Normal => (0.7, 12.0),
+        AiDifficulty::Hard => (1.0, 4.0),
+    };
+
+    let team = paddle.team;
+    let target = match ball {
+        Some((ball, ball_transform))
+            if ball.is_fired() && ball.velocity().dot(team.inward()) < 0.0 =>
+        {
+            ball_transform.translation.dot(team.track())
+        }
+        _ => 0.0,
+    };
+
+    let offset = target - transform.translation.dot(team.track());
+    if offset.abs() < dead_zone {
+        return 0.0;
     }
+
+    (paddle.speed * speed_factor * delta_seconds).min(offset.abs()) * offset.signum()
 }
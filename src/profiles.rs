@@ -0,0 +1,297 @@
+use std::{collections::BTreeMap, fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::MatchWon,
+    files::write_json,
+    player::PlayerType,
+    settings::{AiDifficulty, GameMode, Settings, APP_DIRECTORY},
+};
+
+const PROFILES_FILE: &str = "profiles.json";
+
+const STARTING_RATING: f32 = 1000.0;
+// How far a single match can move a rating
+const ELO_K_FACTOR: f32 = 32.0;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Profile {
+    pub rating: f32,
+    pub wins: u32,
+    pub losses: u32,
+    // Record against each opponent, by name
+    #[serde(default)]
+    pub head_to_head: BTreeMap<String, Record>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile {
+            rating: STARTING_RATING,
+            wins: 0,
+            losses: 0,
+            head_to_head: BTreeMap::new(),
+        }
+    }
+}
+
+// Every player that has finished a match on this machine, by name
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Profiles {
+    profiles: BTreeMap<String, Profile>,
+}
+
+// The names of the profiles playing as each paddle
+#[derive(Resource, Clone, Debug)]
+pub struct PlayerNames {
+    pub player1: String,
+    pub player2: String,
+}
+
+impl PlayerNames {
+    // The computer gets a profile per difficulty, so its rating means something
+    pub fn from_settings(settings: &Settings) -> PlayerNames {
+        let player2 = match settings.ai_difficulty {
+            AiDifficulty::Off => settings.player2_name.clone(),
+            AiDifficulty::Easy => "Computer (easy)".into(),
+            AiDifficulty::Normal => "Computer (normal)".into(),
+            AiDifficulty::Hard => "Computer (hard)".into(),
+        };
+
+        PlayerNames {
+            player1: settings.player1_name.clone(),
+            player2,
+        }
+    }
+
+    // Players 3 and 4 have no profiles, they only play in doubles and the four player modes
+    pub fn name(&self, player: PlayerType) -> &str {
+        match player {
+            PlayerType::Player1 => &self.player1,
+            PlayerType::Player2 => &self.player2,
+            PlayerType::Player3 => "Player 3",
+            PlayerType::Player4 => "Player 4",
+        }
+    }
+}
+
+impl Profiles {
+    // e.g. ~/.local/share/bevy-pong/profiles.json
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(APP_DIRECTORY).join(PROFILES_FILE))
+    }
+
+    // Starts with no profiles when there is no file yet or it can't be read
+    pub fn load() -> Profiles {
+        let Some(path) = Profiles::path() else {
+            return Profiles::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+                warn!(
+                    "Failed to parse {}, starting without profiles: {err}",
+                    path.display()
+                );
+                Profiles::default()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Profiles::default(),
+            Err(err) => {
+                warn!(
+                    "Failed to read {}, starting without profiles: {err}",
+                    path.display()
+                );
+                Profiles::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Profiles::path() else {
+            warn!("No data directory to save profiles to");
+            return;
+        };
+
+        write_json(&path, self, "profiles");
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    // Updates both records and moves the ratings by the standard Elo formula
+    pub fn record_match(&mut self, winner: &str, loser: &str) {
+        let winner_rating = self.profiles.entry(winner.into()).or_default().rating;
+        let loser_rating = self.profiles.entry(loser.into()).or_default().rating;
+
+        let expected = 1.0 / (1.0 + 10f32.powf((loser_rating - winner_rating) / 400.0));
+        let change = ELO_K_FACTOR * (1.0 - expected);
+
+        let winner_profile = self.profiles.entry(winner.into()).or_default();
+        winner_profile.rating += change;
+        winner_profile.wins += 1;
+        winner_profile
+            .head_to_head
+            .entry(loser.into())
+            .or_default()
+            .wins += 1;
+
+        let loser_profile = self.profiles.entry(loser.into()).or_default();
+        loser_profile.rating -= change;
+        loser_profile.losses += 1;
+        loser_profile
+            .head_to_head
+            .entry(winner.into())
+            .or_default()
+            .losses += 1;
+    }
+
+    // Highest rated first
+    pub fn leaderboard(&self) -> Vec<(&str, &Profile)> {
+        let mut leaderboard: Vec<(&str, &Profile)> = self
+            .profiles
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile))
+            .collect();
+        leaderboard.sort_by(|a, b| b.1.rating.total_cmp(&a.1.rating));
+
+        leaderboard
+    }
+
+    pub fn head_to_head(&self, name: &str, opponent: &str) -> Record {
+        self.get(name)
+            .and_then(|profile| profile.head_to_head.get(opponent))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+pub struct ProfilesPlugin;
+
+impl Plugin for ProfilesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Profiles::load())
+            .add_systems(PreStartup, choose_player_names)
+            .add_systems(Update, record_result);
+    }
+}
+
+fn choose_player_names(mut commands: Commands, settings: Res<Settings>) {
+    commands.insert_resource(PlayerNames::from_settings(&settings));
+}
+
+// Ratings are only for one against one, doubles and four player matches aren't recorded
+fn record_result(
+    mut won_events: EventReader<MatchWon>,
+    mut profiles: ResMut<Profiles>,
+    names: Res<PlayerNames>,
+    settings: Res<Settings>,
+) {
+    if settings.mode != GameMode::Classic {
+        won_events.clear();
+        return;
+    }
+
+    for won in won_events.iter() {
+        let loser = match won.winner {
+            PlayerType::Player1 => PlayerType::Player2,
+            _ => PlayerType::Player1,
+        };
+
+        profiles.record_match(names.name(won.winner), names.name(loser));
+        profiles.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(profiles: &Profiles, name: &str) -> f32 {
+        profiles.get(name).expect("a profile").rating
+    }
+
+    #[test]
+    fn evenly_rated_players_move_by_half_the_k_factor() {
+        let mut profiles = Profiles::default();
+        profiles.record_match("Ann", "Bob");
+
+        assert_eq!(
+            rating(&profiles, "Ann"),
+            STARTING_RATING + ELO_K_FACTOR / 2.0
+        );
+        assert_eq!(
+            rating(&profiles, "Bob"),
+            STARTING_RATING - ELO_K_FACTOR / 2.0
+        );
+    }
+
+    #[test]
+    fn upsets_move_ratings_further_than_expected_wins() {
+        let mut profiles = Profiles::default();
+        profiles.record_match("Ann", "Bob");
+        let before = rating(&profiles, "Ann");
+
+        // Ann is now the favourite, so beating Bob again is worth less than the first win
+        profiles.record_match("Ann", "Bob");
+        let expected_win = rating(&profiles, "Ann") - before;
+        assert!(expected_win < ELO_K_FACTOR / 2.0);
+
+        let before = rating(&profiles, "Bob");
+        profiles.record_match("Bob", "Ann");
+        let upset = rating(&profiles, "Bob") - before;
+        assert!(upset > ELO_K_FACTOR / 2.0);
+
+        // Whatever one player gains the other loses
+        let total = rating(&profiles, "Ann") + rating(&profiles, "Bob");
+        assert!((total - 2.0 * STARTING_RATING).abs() < 1e-3);
+    }
+
+    #[test]
+    fn head_to_head_records_are_kept_per_opponent() {
+        let mut profiles = Profiles::default();
+        profiles.record_match("Ann", "Bob");
+        profiles.record_match("Ann", "Bob");
+        profiles.record_match("Bob", "Ann");
+        profiles.record_match("Cat", "Ann");
+
+        let ann_against_bob = profiles.head_to_head("Ann", "Bob");
+        assert_eq!((ann_against_bob.wins, ann_against_bob.losses), (2, 1));
+        let bob_against_ann = profiles.head_to_head("Bob", "Ann");
+        assert_eq!((bob_against_ann.wins, bob_against_ann.losses), (1, 2));
+        let ann_against_cat = profiles.head_to_head("Ann", "Cat");
+        assert_eq!((ann_against_cat.wins, ann_against_cat.losses), (0, 1));
+
+        // Players who never met, or never played at all, have an empty record
+        let bob_against_cat = profiles.head_to_head("Bob", "Cat");
+        assert_eq!((bob_against_cat.wins, bob_against_cat.losses), (0, 0));
+        let unknown = profiles.head_to_head("Dan", "Ann");
+        assert_eq!((unknown.wins, unknown.losses), (0, 0));
+
+        let ann = profiles.get("Ann").expect("a profile");
+        assert_eq!((ann.wins, ann.losses), (2, 2));
+    }
+
+    #[test]
+    fn leaderboard_is_highest_rated_first() {
+        let mut profiles = Profiles::default();
+        profiles.record_match("Bob", "Ann");
+        profiles.record_match("Bob", "Cat");
+        profiles.record_match("Cat", "Ann");
+
+        let names: Vec<&str> = profiles
+            .leaderboard()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(names, ["Bob", "Cat", "Ann"]);
+    }
+}
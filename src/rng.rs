@@ -0,0 +1,43 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+// Small seedable xorshift generator, so matches can be replayed from a seed without
+// depending on an rng crate
+#[derive(Resource)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    // Seeds from the clock when no seed is given
+    pub fn new(seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+
+        // Xorshift gets stuck on zero, so mix the seed first
+        GameRng {
+            state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1,
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Uniform in 0.0..1.0
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+}
@@ -0,0 +1,218 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::ArenaConfig,
+    ascii::StandardOnly,
+    ball::{fire_ball, move_ball, spawn_ball, Ball},
+    events::{BallServed, GoalScored, MatchWon, ScoringSet},
+    player::Paddle,
+    settings::Settings,
+    theme::Theme,
+};
+
+// What happens between goals: play stops for a moment, then counts down to the next serve
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RoundPhase {
+    // Between matches, while the main menu is shown
+    #[default]
+    Menu,
+    // Nothing moves and there is no ball, straight after a goal
+    Freeze,
+    // Paddles can move but there is no ball yet
+    Countdown,
+    // The ball sits on the serving paddle until it's fired
+    Serve,
+    Playing,
+    // Someone has won, the summary is shown until the serve key is pressed
+    MatchOver,
+}
+
+// Paddles only move while a round is being played
+pub fn paddles_can_move(phase: Res<State<RoundPhase>>) -> bool {
+    matches!(
+        phase.get(),
+        RoundPhase::Countdown | RoundPhase::Serve | RoundPhase::Playing
+    )
+}
+
+// The number currently shown by the countdown, None when it isn't running
+#[derive(Resource, Default)]
+pub struct Countdown {
+    pub number: Option<u32>,
+}
+
+impl Countdown {
+    pub fn label(&self) -> String {
+        self.number
+            .map(|number| number.to_string())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Resource, Default)]
+struct RoundTimer(Timer);
+
+#[derive(Component)]
+struct CountdownText;
+
+pub struct RoundPlugin;
+
+impl Plugin for RoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<RoundPhase>()
+            .insert_resource(Countdown::default())
+            .insert_resource(RoundTimer::default())
+            .add_systems(Startup, spawn_countdown_text)
+            .add_systems(OnEnter(RoundPhase::Freeze), start_freeze)
+            .add_systems(OnEnter(RoundPhase::Countdown), start_countdown)
+            .add_systems(OnExit(RoundPhase::Countdown), stop_countdown)
+            .add_systems(OnEnter(RoundPhase::Serve), spawn_ball)
+            .add_systems(
+                Update,
+                (
+                    tick_freeze.run_if(in_state(RoundPhase::Freeze)),
+                    tick_countdown.run_if(in_state(RoundPhase::Countdown)),
+                    // After the ball has been put in front of the server, so an auto serve
+                    // doesn't fire it from wherever it was spawned
+                    serve.after(move_ball).run_if(in_state(RoundPhase::Serve)),
+                    end_round.after(ScoringSet),
+                    leave_match.run_if(in_state(RoundPhase::MatchOver)),
+                    update_countdown_text,
+                ),
+            );
+    }
+}
+
+fn start_freeze(mut timer: ResMut<RoundTimer>, settings: Res<Settings>) {
+    timer.0 = Timer::from_seconds(settings.freeze_seconds, TimerMode::Once);
+}
+
+fn tick_freeze(
+    mut timer: ResMut<RoundTimer>,
+    mut next_phase: ResMut<NextState<RoundPhase>>,
+    mut paddle_query: Query<(&mut Paddle, &mut Transform)>,
+    arena: Res<ArenaConfig>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    if !timer.0.tick(time.delta()).finished() {
+        return;
+    }
+
+    if settings.recentre_paddles {
+        for (mut paddle, mut transform) in paddle_query.iter_mut() {
+            transform.translation = arena.paddle_position(paddle.team, paddle.lane);
+            paddle.velocity = 0.0;
+        }
+    }
+
+    next_phase.set(RoundPhase::Countdown);
+}
+
+fn start_countdown(
+    mut timer: ResMut<RoundTimer>,
+    mut countdown: ResMut<Countdown>,
+    settings: Res<Settings>,
+) {
+    timer.0 = Timer::from_seconds(settings.countdown as f32, TimerMode::Once);
+    countdown.number = (settings.countdown > 0).then_some(settings.countdown);
+}
+
+fn tick_countdown(
+    mut timer: ResMut<RoundTimer>,
+    mut countdown: ResMut<Countdown>,
+    mut next_phase: ResMut<NextState<RoundPhase>>,
+    time: Res<Time>,
+) {
+    if timer.0.tick(time.delta()).finished() {
+        next_phase.set(RoundPhase::Serve);
+        return;
+    }
+
+    let number = Some(timer.0.remaining_secs().ceil() as u32);
+    if countdown.number != number {
+        countdown.number = number;
+    }
+}
+
+fn stop_countdown(mut countdown: ResMut<Countdown>) {
+    countdown.number = None;
+}
+
+// Fires the ball straight away with auto serve, and starts play once it's been fired
+fn serve(
+    mut ball_query: Query<&mut Ball>,
+    mut next_phase: ResMut<NextState<RoundPhase>>,
+    mut served_events: EventWriter<BallServed>,
+    settings: Res<Settings>,
+) {
+    let Ok(mut ball) = ball_query.get_single_mut() else {
+        return;
+    };
+
+    if settings.auto_serve {
+        fire_ball(&mut ball);
+    }
+
+    if ball.is_fired() {
+        served_events.send(BallServed {
+            server: ball.owner(),
+        });
+        next_phase.set(RoundPhase::Playing);
+    }
+}
+
+fn end_round(
+    mut goal_events: EventReader<GoalScored>,
+    mut won_events: EventReader<MatchWon>,
+    mut next_phase: ResMut<NextState<RoundPhase>>,
+) {
+    if goal_events.is_empty() {
+        return;
+    }
+    goal_events.clear();
+
+    next_phase.set(if won_events.is_empty() {
+        RoundPhase::Freeze
+    } else {
+        won_events.clear();
+        RoundPhase::MatchOver
+    });
+}
+
+fn leave_match(keyboard: Res<Input<KeyCode>>, mut next_phase: ResMut<NextState<RoundPhase>>) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        next_phase.set(RoundPhase::Menu);
+    }
+}
+
+fn spawn_countdown_text(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let text_style = TextStyle {
+        font: asset_server.load(theme.font.as_str()),
+        font_size: 120.0,
+        color: theme.text,
+    };
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", text_style),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+            ..default()
+        },
+        CountdownText,
+        StandardOnly,
+    ));
+}
+
+fn update_countdown_text(
+    countdown: Res<Countdown>,
+    mut text_query: Query<&mut Text, With<CountdownText>>,
+) {
+    if !countdown.is_changed() {
+        return;
+    }
+
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = countdown.label();
+    }
+}
@@ -1,26 +1,76 @@
 use crate::{
-    audio::play_win,
-    ball::{spawn_ball, Ball, LastOwner, BALL_SIZE},
-    player::{Paddle, PlayerType},
-    ARENA_HEIGHT, ARENA_WIDTH,
+    arena::ArenaConfig,
+    ball::{Ball, BALL_SIZE},
+    events::{GoalScored, MatchWon, ScoringSet},
+    player::PlayerType,
+    settings::{four_player_mode, Settings},
 };
-use bevy::a11y::accesskit::{NodeBuilder, Role};
 use bevy::prelude::*;
-use bevy_kira_audio::Audio;
 
-const GOAL_WIDTH: f32 = 100.0;
+pub const DEFAULT_POINTS_TO_WIN: u32 = 11;
 
 #[derive(Resource)]
 
-struct Score {
+pub struct Score {
     player1_score: u32,
     player2_score: u32,
+    points_to_win: u32,
 }
 
-#[derive(Component)]
+impl Score {
+    // Only the classic mode keeps score, the four player modes count lives instead
+    pub fn points(&self, player: PlayerType) -> u32 {
+        match player {
+            PlayerType::Player1 => self.player1_score,
+            PlayerType::Player2 => self.player2_score,
+            PlayerType::Player3 | PlayerType::Player4 => 0,
+        }
+    }
+
+    pub fn points_to_win(&self) -> u32 {
+        self.points_to_win
+    }
+
+    // True when either player is one point away from winning
+    pub fn is_match_point(&self) -> bool {
+        self.player1_score + 1 >= self.points_to_win
+            || self.player2_score + 1 >= self.points_to_win
+    }
+
+    pub fn reset(&mut self) {
+        self.player1_score = 0;
+        self.player2_score = 0;
+    }
+
+    pub fn winner(&self) -> Option<PlayerType> {
+        if self.player1_score >= self.points_to_win {
+            Some(PlayerType::Player1)
+        } else if self.player2_score >= self.points_to_win {
+            Some(PlayerType::Player2)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy)]
 pub enum GoalFor {
     Player1,
     Player2,
+    Player3,
+    Player4,
+}
+
+impl GoalFor {
+    // The player defending this goal
+    pub fn player(self) -> PlayerType {
+        match self {
+            GoalFor::Player1 => PlayerType::Player1,
+            GoalFor::Player2 => PlayerType::Player2,
+            GoalFor::Player3 => PlayerType::Player3,
+            GoalFor::Player4 => PlayerType::Player4,
+        }
+    }
 }
 
 #[derive(Component)]
@@ -31,78 +81,49 @@ impl Default for Score {
         Score {
             player1_score: 0,
             player2_score: 0,
+            points_to_win: DEFAULT_POINTS_TO_WIN,
         }
     }
 }
 
-pub struct ScorePlugin;
+pub struct ScorePlugin {
+    pub points_to_win: u32,
+}
+
+impl Default for ScorePlugin {
+    fn default() -> Self {
+        ScorePlugin {
+            points_to_win: DEFAULT_POINTS_TO_WIN,
+        }
+    }
+}
 
 impl Plugin for ScorePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Score::default())
-            .add_systems(Startup, (setup_ui, spawn_goals))
-            .add_systems(Update, (check_goal_collision));
+        app.insert_resource(Score {
+            points_to_win: self.points_to_win,
+            ..default()
+        })
+            .configure_set(Update, ScoringSet.after(check_goal_collision))
+            .add_systems(Startup, spawn_goals)
+            .add_systems(
+                Update,
+                (
+                    check_goal_collision,
+                    award_point.in_set(ScoringSet).run_if(not(four_player_mode)),
+                )
+                    .chain(),
+            );
     }
 }
 
-fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
-    // Load a font
-    // root node
-    let font = asset_server.load("fonts/Minecraft.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 60.0,
-        color: Color::WHITE,
-    };
-    let text_alignment = TextAlignment::Left;
-    let text_alignment = TextAlignment::Left;
-
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("00", text_style.clone()).with_alignment(text_alignment),
-            transform: Transform::from_translation(Vec3::new(
-                -40.0,
-                ARENA_HEIGHT / 2.0 + 50.0,
-                1.0,
-            )),
-
-            ..default()
-        },
-        PlayerType::Player1,
-    ));
-    commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("00", text_style.clone()).with_alignment(text_alignment),
-            // ensure the text is drawn on top of the box
-            transform: Transform::from_translation(Vec3::new(40.0, ARENA_HEIGHT / 2.0 + 50.0, 1.0)),
-            ..default()
-        },
-        PlayerType::Player2,
-    ));
+fn spawn_goals(mut commands: Commands, arena: Res<ArenaConfig>) {
+    spawn_goal(&mut commands, &arena, GoalFor::Player1);
+    spawn_goal(&mut commands, &arena, GoalFor::Player2);
 }
 
-fn spawn_goals(mut commands: Commands) {
-    commands.spawn((
-        SpriteBundle {
-            sprite: Sprite {
-                color: Color::Hsla {
-                    hue: (0.0),
-                    saturation: (0.0),
-                    lightness: (0.0),
-                    alpha: (0.0),
-                },
-                custom_size: Some(Vec2::new(GOAL_WIDTH, ARENA_HEIGHT)),
-                ..default()
-            },
-            transform: Transform {
-                translation: Vec3::new(-ARENA_WIDTH / 2.0 - GOAL_WIDTH * 2.0, 0.0, 0.0),
-                ..default()
-            },
-            ..default()
-        },
-        Goal,
-        GoalFor::Player1,
-    ));
+pub fn spawn_goal(commands: &mut Commands, arena: &ArenaConfig, goal_for: GoalFor) {
+    let player = goal_for.player();
 
     commands.spawn((
         SpriteBundle {
@@ -113,68 +134,51 @@ fn spawn_goals(mut commands: Commands) {
                     lightness: (0.0),
                     alpha: (0.0),
                 },
-                custom_size: Some(Vec2::new(GOAL_WIDTH, ARENA_HEIGHT)),
+                custom_size: Some(arena.goal_size(player)),
                 ..default()
             },
             transform: Transform {
-                translation: Vec3::new(ARENA_WIDTH / 2.0 + GOAL_WIDTH * 2.0, 0.0, 0.0),
+                translation: arena.goal_position(player),
                 ..default()
             },
             ..default()
         },
         Goal,
-        GoalFor::Player2,
+        goal_for,
     ));
 }
+
 fn check_goal_collision(
     mut commands: Commands,
-    mut ball_query: Query<(Entity, &Transform), With<Ball>>,
-    lastOwner: ResMut<LastOwner>,
+    ball_query: Query<(Entity, &Ball, &Transform)>,
     goal_query: Query<(&GoalFor, &Transform), With<Goal>>,
-    mut score: ResMut<Score>,
-    asset_server: Res<AssetServer>,
-    audio: Res<Audio>,
-    mut score_query: Query<(&PlayerType, &mut Text)>,
+    arena: Res<ArenaConfig>,
+    settings: Res<Settings>,
+    mut goal_events: EventWriter<GoalScored>,
 ) {
-    let mut ball_to_despawn = None;
-
-    for (ball_entity, ball_transform) in ball_query.iter() {
+    for (ball_entity, ball, ball_transform) in ball_query.iter() {
         for (goal_for, goal_transform) in goal_query.iter() {
-            if check_collision(ball_transform, goal_transform) {
-                // Determine which player scored based on the goal hit
-                let scoring_player = match goal_for {
-                    GoalFor::Player1 => GoalFor::Player2,
-                    GoalFor::Player2 => GoalFor::Player1,
+            let conceded = goal_for.player();
+
+            if check_collision(ball_transform, goal_transform, arena.goal_size(conceded)) {
+                // The goal belongs to the player defending it. In the classic mode the other
+                // player scored, with four players it's whoever hit the ball last.
+                let scorer = match conceded {
+                    _ if settings.mode.is_four_player() => ball.owner(),
+                    PlayerType::Player1 => PlayerType::Player2,
+                    _ => PlayerType::Player1,
                 };
 
-                // Update the score and the UI
-                update_player_score(&mut score, scoring_player, &mut score_query);
-
-                play_win(&asset_server, &audio);
-
-                ball_to_despawn = Some(ball_entity);
+                // The ball is out of play, the next one is served once the round has reset
+                commands.entity(ball_entity).despawn_recursive();
+                goal_events.send(GoalScored { scorer, conceded });
                 break;
             }
         }
-
-        if ball_to_despawn.is_some() {
-            break;
-        }
-    }
-
-    if let Some(ball_entity) = ball_to_despawn {
-        // Despawn current ball
-        commands.entity(ball_entity).despawn();
-
-        // Spawn new ball
-        spawn_ball(commands, lastOwner);
     }
 }
 
-fn check_collision(ball_transform: &Transform, goal_transform: &Transform) -> bool {
-    // Define the paddle size (adjust these values according to your paddle size)
-    let goal_size = Vec2::new(100.0, ARENA_HEIGHT);
-
+fn check_collision(ball_transform: &Transform, goal_transform: &Transform, goal_size: Vec2) -> bool {
     let ball_pos = ball_transform.translation;
     let goal_pos = goal_transform.translation;
 
@@ -185,28 +189,20 @@ fn check_collision(ball_transform: &Transform, goal_transform: &Transform) -> bo
         && ball_pos.y - BALL_SIZE / 2.0 < goal_pos.y + goal_size.y / 2.0
 }
 
-fn update_player_score(
-    score: &mut Score,
-    scoring_player: GoalFor,
-    score_query: &mut Query<(&PlayerType, &mut Text)>,
+fn award_point(
+    mut goal_events: EventReader<GoalScored>,
+    mut score: ResMut<Score>,
+    mut match_won: EventWriter<MatchWon>,
 ) {
-    match scoring_player {
-        GoalFor::Player1 => {
-            score.player1_score += 1;
+    for goal in goal_events.iter() {
+        match goal.scorer {
+            PlayerType::Player1 => score.player1_score += 1,
+            PlayerType::Player2 => score.player2_score += 1,
+            PlayerType::Player3 | PlayerType::Player4 => {}
         }
-        GoalFor::Player2 => {
-            score.player2_score += 1;
-        }
-    }
 
-    for (player_type, mut text) in score_query.iter_mut() {
-        match player_type {
-            PlayerType::Player1 => {
-                text.sections[0].value = format!("{:02}", score.player1_score);
-            }
-            PlayerType::Player2 => {
-                text.sections[0].value = format!("{:02}", score.player2_score);
-            }
+        if let Some(winner) = score.winner() {
+            match_won.send(MatchWon { winner });
         }
     }
 }
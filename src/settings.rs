@@ -0,0 +1,738 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use bevy_kira_audio::prelude::*;
+use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arena::check_arena_name,
+    rng::GameRng,
+    score::DEFAULT_POINTS_TO_WIN,
+    theme::check_theme_name,
+    tournament::{Elimination, MAX_TOURNAMENT_PLAYERS, MIN_TOURNAMENT_PLAYERS},
+};
+
+const SETTINGS_FILE: &str = "settings.toml";
+// Directory for this game under the user's config and data directories
+pub const APP_DIRECTORY: &str = "bevy-pong";
+
+const MIN_WINDOW_WIDTH: f32 = 320.0;
+const MIN_WINDOW_HEIGHT: f32 = 240.0;
+const MAX_POINTS_TO_WIN: u32 = 99;
+const MAX_FREEZE_SECONDS: f32 = 10.0;
+const MAX_COUNTDOWN: u32 = 9;
+const MAX_LIVES: u32 = 9;
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AiDifficulty {
+    // Both paddles are controlled from the keyboard
+    #[default]
+    Off,
+    Easy,
+    Normal,
+    Hard,
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum GameMode {
+    // Two paddles, first to the points to win
+    #[default]
+    Classic,
+    // Two paddles on each side, one forward and one back, played to the points to win
+    Doubles,
+    // Four paddles, one on each side, and the last player with lives left wins
+    FreeForAll,
+    // Four paddles, left and top against right and bottom
+    Teams,
+}
+
+impl GameMode {
+    // Whether every side has a goal and players have lives instead of points
+    pub fn is_four_player(self) -> bool {
+        matches!(self, GameMode::FreeForAll | GameMode::Teams)
+    }
+
+    pub fn has_four_paddles(self) -> bool {
+        self != GameMode::Classic
+    }
+}
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PaddlePhysics {
+    // Paddles move at full speed while a key is held and stop dead when it's let go
+    #[default]
+    Instant,
+    // Paddles speed up and slow down using each character's handling, and double tapping a
+    // direction dashes
+    Momentum,
+}
+
+// Run condition for systems that only apply with paddles on all four sides
+pub fn four_player_mode(settings: Res<Settings>) -> bool {
+    settings.mode.is_four_player()
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Pong, with settings read from settings.toml in the user's config directory")]
+pub struct Cli {
+    /// Read settings from this file instead of the user's config directory
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Print the effective settings as TOML and exit
+    #[arg(long)]
+    pub print_config: bool,
+    #[arg(long)]
+    pub window_width: Option<f32>,
+    #[arg(long)]
+    pub window_height: Option<f32>,
+    #[arg(long, conflicts_with = "windowed")]
+    pub fullscreen: bool,
+    #[arg(long)]
+    pub windowed: bool,
+    /// Volume between 0.0 and 1.0
+    #[arg(long)]
+    pub volume: Option<f64>,
+    #[arg(long)]
+    pub points_to_win: Option<u32>,
+    /// Difficulty of the computer controlled paddles, every paddle but the left one
+    #[arg(long, value_enum)]
+    pub ai_difficulty: Option<AiDifficulty>,
+    /// Classic one against one, or one of the four paddle modes
+    #[arg(long, value_enum)]
+    pub mode: Option<GameMode>,
+    /// Lives each player starts with in the four player modes
+    #[arg(long)]
+    pub lives: Option<u32>,
+    /// How the paddles move, instantly or with acceleration and momentum
+    #[arg(long, value_enum)]
+    pub paddle_physics: Option<PaddlePhysics>,
+    /// Name of the arena from assets/arenas.ron to start in
+    #[arg(long, value_name = "NAME")]
+    pub map: Option<String>,
+    /// Name of the theme from assets/default.themes.ron to draw the game with
+    #[arg(long, value_name = "NAME")]
+    pub theme: Option<String>,
+    /// Seed for anything random in a match, such as who serves first
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Seconds counted down before each serve, 0 to serve straight away
+    #[arg(long, value_name = "SECONDS")]
+    pub countdown: Option<u32>,
+    /// Fire the ball as soon as the countdown ends instead of waiting for the server
+    #[arg(long)]
+    pub auto_serve: bool,
+    /// Let both paddles on a side hit the ball before the other side returns it, in doubles
+    #[arg(long)]
+    pub allow_double_hits: bool,
+    /// Turn off the ball trail, hit particles, screen shake and paddle squash
+    #[arg(long)]
+    pub no_effects: bool,
+    /// Draw the arena like an old CRT monitor, F3 switches it on and off while playing
+    #[arg(long)]
+    pub crt: bool,
+    /// Play a tone whose pitch follows the ball's height above the left paddle
+    #[arg(long)]
+    pub audio_cues: bool,
+    /// Profile name for the left paddle
+    #[arg(long, value_name = "NAME")]
+    pub player1: Option<String>,
+    /// Profile name for the right paddle, unless the computer is playing it
+    #[arg(long, value_name = "NAME")]
+    pub player2: Option<String>,
+    /// Start a new tournament between these players, replacing any saved one
+    #[arg(long, value_name = "NAMES", value_delimiter = ',', num_args = 1..)]
+    pub tournament: Vec<String>,
+    /// Bracket for a new tournament
+    #[arg(long, value_enum, default_value_t = Elimination::Single)]
+    pub elimination: Elimination,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub window_width: f32,
+    pub window_height: f32,
+    pub fullscreen: bool,
+    pub volume: f64,
+    pub points_to_win: u32,
+    pub ai_difficulty: AiDifficulty,
+    pub mode: GameMode,
+    pub lives: u32,
+    pub paddle_physics: PaddlePhysics,
+    pub starting_map: Option<String>,
+    pub theme: Option<String>,
+    pub seed: Option<u64>,
+    // Seconds play stays frozen after a goal, before the countdown starts
+    pub freeze_seconds: f32,
+    pub countdown: u32,
+    // Move both paddles back to the middle after each goal
+    pub recentre_paddles: bool,
+    pub auto_serve: bool,
+    // In doubles, whether partners may both hit the ball before the other side returns it
+    pub allow_double_hits: bool,
+    // Visual effects in the windowed game, the shake and squash can be turned off for players
+    // who are sensitive to motion
+    pub ball_trail: bool,
+    pub hit_particles: bool,
+    pub screen_shake: bool,
+    pub paddle_squash: bool,
+    // The CRT look in the windowed game, and how strong each part of it is from 0.0 to 1.0
+    pub crt: bool,
+    pub crt_scanlines: f32,
+    pub crt_curvature: f32,
+    pub crt_bloom: f32,
+    pub crt_aberration: f32,
+    // A tone that follows the ball, for players who can't see it
+    pub audio_cues: bool,
+    // Names of the profiles the match results are recorded against
+    pub player1_name: String,
+    pub player2_name: String,
+    // Only ever given on the command line, a saved tournament is resumed otherwise
+    #[serde(skip)]
+    pub tournament_players: Vec<String>,
+    #[serde(skip)]
+    pub elimination: Elimination,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            window_width: 1000.0,
+            window_height: 800.0,
+            fullscreen: false,
+            volume: 1.0,
+            points_to_win: DEFAULT_POINTS_TO_WIN,
+            ai_difficulty: AiDifficulty::Off,
+            mode: GameMode::Classic,
+            lives: 3,
+            paddle_physics: PaddlePhysics::Instant,
+            starting_map: None,
+            theme: None,
+            seed: None,
+            freeze_seconds: 1.0,
+            countdown: 3,
+            recentre_paddles: true,
+            auto_serve: false,
+            allow_double_hits: false,
+            ball_trail: true,
+            hit_particles: true,
+            screen_shake: true,
+            paddle_squash: true,
+            crt: false,
+            crt_scanlines: 0.5,
+            crt_curvature: 0.5,
+            crt_bloom: 0.5,
+            crt_aberration: 0.3,
+            audio_cues: false,
+            player1_name: "Player 1".into(),
+            player2_name: "Player 2".into(),
+            tournament_players: Vec::new(),
+            elimination: Elimination::default(),
+        }
+    }
+}
+
+// Makes the settings and a generator seeded from them available as resources
+pub struct SettingsPlugin {
+    pub settings: Settings,
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .insert_resource(GameRng::new(self.settings.seed))
+            .add_systems(Startup, apply_volume);
+    }
+}
+
+fn apply_volume(settings: Res<Settings>, audio: Res<Audio>) {
+    audio.set_volume(settings.volume);
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    Invalid {
+        field: &'static str,
+        reason: String,
+    },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Read { path, source } => {
+                write!(f, "could not read {}: {source}", path.display())
+            }
+            SettingsError::Parse { path, source } => {
+                write!(f, "could not parse {}: {source}", path.display())
+            }
+            SettingsError::Invalid { field, reason } => write!(f, "invalid {field}: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+impl Settings {
+    // Where settings are read from when no --config is given, e.g. ~/.config/bevy-pong/settings.toml
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join(APP_DIRECTORY).join(SETTINGS_FILE))
+    }
+
+    // Reads the settings file, then applies the command line on top. A missing file in the
+    // config directory just means the defaults are used, a missing --config file is an error.
+    pub fn load(cli: &Cli) -> Result<Settings, SettingsError> {
+        let mut settings = match (&cli.config, Settings::default_path()) {
+            (Some(path), _) => Settings::read(path)?,
+            (None, Some(path)) if path.exists() => Settings::read(&path)?,
+            _ => Settings::default(),
+        };
+
+        settings.apply_cli(cli);
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    fn read(path: &Path) -> Result<Settings, SettingsError> {
+        let contents = fs::read_to_string(path).map_err(|source| SettingsError::Read {
+            path: path.to_owned(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|source| SettingsError::Parse {
+            path: path.to_owned(),
+            source,
+        })
+    }
+
+    fn apply_cli(&mut self, cli: &Cli) {
+        if let Some(window_width) = cli.window_width {
+            self.window_width = window_width;
+        }
+        if let Some(window_height) = cli.window_height {
+            self.window_height = window_height;
+        }
+        if cli.fullscreen {
+            self.fullscreen = true;
+        }
+        if cli.windowed {
+            self.fullscreen = false;
+        }
+        if let Some(volume) = cli.volume {
+            self.volume = volume;
+        }
+        if let Some(points_to_win) = cli.points_to_win {
+            self.points_to_win = points_to_win;
+        }
+        if let Some(ai_difficulty) = cli.ai_difficulty {
+            self.ai_difficulty = ai_difficulty;
+        }
+        if let Some(mode) = cli.mode {
+            self.mode = mode;
+        }
+        if let Some(lives) = cli.lives {
+            self.lives = lives;
+        }
+        if let Some(paddle_physics) = cli.paddle_physics {
+            self.paddle_physics = paddle_physics;
+        }
+        if let Some(map) = &cli.map {
+            self.starting_map = Some(map.clone());
+        }
+        if let Some(theme) = &cli.theme {
+            self.theme = Some(theme.clone());
+        }
+        if let Some(seed) = cli.seed {
+            self.seed = Some(seed);
+        }
+        if let Some(countdown) = cli.countdown {
+            self.countdown = countdown;
+        }
+        if cli.auto_serve {
+            self.auto_serve = true;
+        }
+        if cli.allow_double_hits {
+            self.allow_double_hits = true;
+        }
+        if cli.no_effects {
+            self.ball_trail = false;
+            self.hit_particles = false;
+            self.screen_shake = false;
+            self.paddle_squash = false;
+        }
+        if cli.crt {
+            self.crt = true;
+        }
+        if cli.audio_cues {
+            self.audio_cues = true;
+        }
+        if let Some(player1) = &cli.player1 {
+            self.player1_name = player1.clone();
+        }
+        if let Some(player2) = &cli.player2 {
+            self.player2_name = player2.clone();
+        }
+        self.tournament_players = cli
+            .tournament
+            .iter()
+            .map(|name| name.trim().to_string())
+            .collect();
+        self.elimination = cli.elimination;
+    }
+
+    fn validate(&self) -> Result<(), SettingsError> {
+        if !self.window_width.is_finite() || !(MIN_WINDOW_WIDTH..).contains(&self.window_width) {
+            return Err(SettingsError::Invalid {
+                field: "window_width",
+                reason: format!(
+                    "must be a finite number of at least {MIN_WINDOW_WIDTH}, got {}",
+                    self.window_width
+                ),
+            });
+        }
+        if !self.window_height.is_finite() || !(MIN_WINDOW_HEIGHT..).contains(&self.window_height) {
+            return Err(SettingsError::Invalid {
+                field: "window_height",
+                reason: format!(
+                    "must be a finite number of at least {MIN_WINDOW_HEIGHT}, got {}",
+                    self.window_height
+                ),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.volume) {
+            return Err(SettingsError::Invalid {
+                field: "volume",
+                reason: format!("must be between 0.0 and 1.0, got {}", self.volume),
+            });
+        }
+        if !(1..=MAX_POINTS_TO_WIN).contains(&self.points_to_win) {
+            return Err(SettingsError::Invalid {
+                field: "points_to_win",
+                reason: format!(
+                    "must be between 1 and {MAX_POINTS_TO_WIN}, got {}",
+                    self.points_to_win
+                ),
+            });
+        }
+        if let Some(map) = &self.starting_map {
+            if map.trim().is_empty() {
+                return Err(SettingsError::Invalid {
+                    field: "starting_map",
+                    reason: "must not be empty".into(),
+                });
+            }
+            check_arena_name(map).map_err(|reason| SettingsError::Invalid {
+                field: "starting_map",
+                reason,
+            })?;
+        }
+        if let Some(theme) = &self.theme {
+            if theme.trim().is_empty() {
+                return Err(SettingsError::Invalid {
+                    field: "theme",
+                    reason: "must not be empty".into(),
+                });
+            }
+            check_theme_name(theme).map_err(|reason| SettingsError::Invalid {
+                field: "theme",
+                reason,
+            })?;
+        }
+        if !(0.0..=MAX_FREEZE_SECONDS).contains(&self.freeze_seconds) {
+            return Err(SettingsError::Invalid {
+                field: "freeze_seconds",
+                reason: format!(
+                    "must be between 0 and {MAX_FREEZE_SECONDS}, got {}",
+                    self.freeze_seconds
+                ),
+            });
+        }
+        for (field, intensity) in [
+            ("crt_scanlines", self.crt_scanlines),
+            ("crt_curvature", self.crt_curvature),
+            ("crt_bloom", self.crt_bloom),
+            ("crt_aberration", self.crt_aberration),
+        ] {
+            if !(0.0..=1.0).contains(&intensity) {
+                return Err(SettingsError::Invalid {
+                    field,
+                    reason: format!("must be between 0.0 and 1.0, got {intensity}"),
+                });
+            }
+        }
+        for (field, name) in [
+            ("player1_name", &self.player1_name),
+            ("player2_name", &self.player2_name),
+        ] {
+            if name.trim().is_empty() {
+                return Err(SettingsError::Invalid {
+                    field,
+                    reason: "must not be empty".into(),
+                });
+            }
+        }
+        // Both players' results would go on the same profile
+        if self.player1_name.trim().to_lowercase() == self.player2_name.trim().to_lowercase() {
+            return Err(SettingsError::Invalid {
+                field: "player2_name",
+                reason: format!(
+                    "must be different to player1_name, both are {:?}",
+                    self.player1_name.trim()
+                ),
+            });
+        }
+        if !self.tournament_players.is_empty() {
+            let players = &self.tournament_players;
+            let count = players.len();
+
+            if !(MIN_TOURNAMENT_PLAYERS..=MAX_TOURNAMENT_PLAYERS).contains(&count) {
+                return Err(SettingsError::Invalid {
+                    field: "tournament",
+                    reason: format!(
+                        "needs {MIN_TOURNAMENT_PLAYERS} to {MAX_TOURNAMENT_PLAYERS} players, got {count}"
+                    ),
+                });
+            }
+            if players.iter().any(|name| name.trim().is_empty()) {
+                return Err(SettingsError::Invalid {
+                    field: "tournament",
+                    reason: "player names must not be empty".into(),
+                });
+            }
+            // Entrants are told apart the same way as the two players' names
+            let same = |a: &String, b: &String| a.trim().to_lowercase() == b.trim().to_lowercase();
+            if let Some(name) = players.iter().enumerate().find_map(|(index, name)| {
+                players[..index]
+                    .iter()
+                    .any(|other| same(other, name))
+                    .then_some(name)
+            }) {
+                return Err(SettingsError::Invalid {
+                    field: "tournament",
+                    reason: format!("{name} is entered more than once"),
+                });
+            }
+            if self.mode != GameMode::Classic {
+                return Err(SettingsError::Invalid {
+                    field: "tournament",
+                    reason: "can only be played in the classic mode".into(),
+                });
+            }
+            // The computer would play every match for the second entrant, and the results
+            // would still go on their profile
+            if self.ai_difficulty != AiDifficulty::Off {
+                return Err(SettingsError::Invalid {
+                    field: "tournament",
+                    reason: "can't be played against the computer, ai_difficulty must be off"
+                        .into(),
+                });
+            }
+        }
+        if !(1..=MAX_LIVES).contains(&self.lives) {
+            return Err(SettingsError::Invalid {
+                field: "lives",
+                reason: format!("must be between 1 and {MAX_LIVES}, got {}", self.lives),
+            });
+        }
+        if self.countdown > MAX_COUNTDOWN {
+            return Err(SettingsError::Invalid {
+                field: "countdown",
+                reason: format!("must be at most {MAX_COUNTDOWN}, got {}", self.countdown),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+// Parses the command line and loads the settings, printing any error and exiting when they
+// can't be used. Also handles --print-config.
+pub fn load_settings_or_exit() -> Settings {
+    let cli = Cli::parse();
+
+    let settings = Settings::load(&cli).unwrap_or_else(|err| {
+        eprintln!("error: {err}");
+        std::process::exit(2);
+    });
+
+    if cli.print_config {
+        match toml::to_string_pretty(&settings) {
+            Ok(toml) => print!("{toml}"),
+            Err(err) => {
+                eprintln!("error: could not print settings: {err}");
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The field named by the validation error, if the settings are rejected
+    fn rejected_field(settings: &Settings) -> Option<&'static str> {
+        match settings.validate() {
+            Ok(()) => None,
+            Err(SettingsError::Invalid { field, .. }) => Some(field),
+            Err(error) => panic!("unexpected error: {error}"),
+        }
+    }
+
+    fn tournament(players: &[&str]) -> Settings {
+        Settings {
+            tournament_players: players.iter().map(|name| name.to_string()).collect(),
+            ..default()
+        }
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert_eq!(rejected_field(&Settings::default()), None);
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let cases = [
+            (
+                Settings {
+                    window_width: MIN_WINDOW_WIDTH - 1.0,
+                    ..default()
+                },
+                "window_width",
+            ),
+            (
+                Settings {
+                    window_width: f32::INFINITY,
+                    ..default()
+                },
+                "window_width",
+            ),
+            (
+                Settings {
+                    window_height: f32::NAN,
+                    ..default()
+                },
+                "window_height",
+            ),
+            (
+                Settings {
+                    volume: 1.5,
+                    ..default()
+                },
+                "volume",
+            ),
+            (
+                Settings {
+                    points_to_win: 0,
+                    ..default()
+                },
+                "points_to_win",
+            ),
+            (
+                Settings {
+                    freeze_seconds: -1.0,
+                    ..default()
+                },
+                "freeze_seconds",
+            ),
+            (
+                Settings {
+                    crt_bloom: 2.0,
+                    ..default()
+                },
+                "crt_bloom",
+            ),
+            (
+                Settings {
+                    lives: MAX_LIVES + 1,
+                    ..default()
+                },
+                "lives",
+            ),
+            (
+                Settings {
+                    countdown: MAX_COUNTDOWN + 1,
+                    ..default()
+                },
+                "countdown",
+            ),
+        ];
+
+        for (settings, field) in cases {
+            assert_eq!(rejected_field(&settings), Some(field));
+        }
+    }
+
+    #[test]
+    fn players_need_different_names() {
+        let settings = Settings {
+            player1_name: "Alice".into(),
+            player2_name: " alice ".into(),
+            ..default()
+        };
+        assert_eq!(rejected_field(&settings), Some("player2_name"));
+
+        let settings = Settings {
+            player1_name: "  ".into(),
+            ..default()
+        };
+        assert_eq!(rejected_field(&settings), Some("player1_name"));
+    }
+
+    #[test]
+    fn tournament_entrants_must_be_different() {
+        assert_eq!(
+            rejected_field(&tournament(&["Ann", "Bob", "Cat", "Dan"])),
+            None
+        );
+        assert_eq!(
+            rejected_field(&tournament(&["Ann", "Bob", "Cat", " ann"])),
+            Some("tournament")
+        );
+        assert_eq!(
+            rejected_field(&tournament(&["Ann", "", "Cat", "Dan"])),
+            Some("tournament")
+        );
+        assert_eq!(
+            rejected_field(&tournament(&["Ann", "Bob", "Cat"])),
+            Some("tournament")
+        );
+    }
+
+    #[test]
+    fn tournaments_are_classic_without_the_computer() {
+        for mode in [GameMode::Doubles, GameMode::FreeForAll, GameMode::Teams] {
+            let settings = Settings {
+                mode,
+                ..tournament(&["Ann", "Bob", "Cat", "Dan"])
+            };
+            assert_eq!(rejected_field(&settings), Some("tournament"));
+        }
+
+        let settings = Settings {
+            ai_difficulty: AiDifficulty::Normal,
+            ..tournament(&["Ann", "Bob", "Cat", "Dan"])
+        };
+        assert_eq!(rejected_field(&settings), Some("tournament"));
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{
+    arena::ArenaConfig,
+    ball::{move_ball, Ball},
+    characters::Special,
+    events::PaddleHit,
+    player::{Paddle, PlayerType},
+    round::{paddles_can_move, RoundPhase},
+    settings::{AiDifficulty, Settings},
+};
+
+// Returns needed to fill the special meter
+const RETURNS_PER_CHARGE: f32 = 4.0;
+// Spin a curve shot leaves the paddle with, more than a moving paddle can put on
+const CURVE_SPIN: f32 = 25.0;
+const SMASH_BOOST: f32 = 2.0;
+const DASH_SECONDS: f32 = 0.3;
+
+// Thickness of the charge meter drawn in front of each paddle
+const METER_WIDTH: f32 = 3.0;
+
+#[derive(Component)]
+struct ChargeMeter;
+
+// The meter as text for the terminal, e.g. "[##--]", or "[****]" while a special is armed
+pub fn meter_label(paddle: &Paddle) -> String {
+    let cells = RETURNS_PER_CHARGE as usize;
+    let filled = (paddle.charge * RETURNS_PER_CHARGE).floor() as usize;
+
+    if paddle.armed {
+        format!("[{}]", "*".repeat(cells))
+    } else {
+        format!("[{}{}]", "#".repeat(filled), "-".repeat(cells - filled))
+    }
+}
+
+pub struct SpecialsPlugin;
+
+impl Plugin for SpecialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(RoundPhase::Menu), reset_charge)
+            .add_systems(
+                Update,
+                (
+                    use_specials.run_if(paddles_can_move),
+                    charge_specials.after(move_ball),
+                    spawn_charge_meters,
+                    update_charge_meters,
+                ),
+            );
+    }
+}
+
+// Every match starts with empty meters
+fn reset_charge(mut paddle_query: Query<&mut Paddle>) {
+    for mut paddle in paddle_query.iter_mut() {
+        paddle.charge = 0.0;
+        paddle.armed = false;
+        paddle.dash_seconds = 0.0;
+    }
+}
+
+// A full meter is spent when the special key is pressed. Dashes start straight away, the
+// other specials are used on the paddle's next return.
+fn use_specials(
+    mut paddle_query: Query<&mut Paddle>,
+    ball_query: Query<&Ball>,
+    keyboard: Res<Input<KeyCode>>,
+    settings: Res<Settings>,
+) {
+    let ball = ball_query.get_single().ok();
+
+    for mut paddle in paddle_query.iter_mut() {
+        if paddle.charge < 1.0 || paddle.special == Special::None {
+            continue;
+        }
+
+        let pressed = match paddle.player_type {
+            // The computer uses its special once the ball is coming towards it
+            PlayerType::Player2 | PlayerType::Player3 | PlayerType::Player4
+                if settings.ai_difficulty != AiDifficulty::Off =>
+            {
+                ball.is_some_and(|ball| {
+                    ball.is_fired() && ball.velocity().dot(paddle.team.inward()) < 0.0
+                })
+            }
+            PlayerType::Player1 => keyboard.just_pressed(KeyCode::D),
+            PlayerType::Player2 => keyboard.just_pressed(KeyCode::Slash),
+            PlayerType::Player3 => keyboard.just_pressed(KeyCode::O),
+            PlayerType::Player4 => keyboard.just_pressed(KeyCode::End),
+        };
+        if !pressed {
+            continue;
+        }
+
+        paddle.charge = 0.0;
+        if paddle.special == Special::Dash {
+            paddle.dash_seconds = DASH_SECONDS;
+        } else {
+            paddle.armed = true;
+        }
+    }
+}
+
+// Returning the ball fills the meter, and sets off an armed special
+fn charge_specials(
+    mut hit_events: EventReader<PaddleHit>,
+    mut paddle_query: Query<&mut Paddle>,
+    mut ball_query: Query<&mut Ball>,
+) {
+    for hit in hit_events.iter() {
+        for mut paddle in paddle_query.iter_mut() {
+            if paddle.player_type != hit.player {
+                continue;
+            }
+
+            paddle.charge = (paddle.charge + 1.0 / RETURNS_PER_CHARGE).min(1.0);
+
+            if !paddle.armed {
+                continue;
+            }
+            paddle.armed = false;
+
+            let Ok(mut ball) = ball_query.get_single_mut() else {
+                continue;
+            };
+
+            match paddle.special {
+                // The ball heads off the way the paddle was moving, then bends back the other way
+                Special::CurveShot => {
+                    let moving = if paddle.velocity < 0.0 { -1.0 } else { 1.0 };
+                    let velocity = ball.velocity();
+                    let left = Vec3::new(-velocity.y, velocity.x, 0.0);
+                    let turn = left.dot(-paddle.team.track() * moving).signum();
+
+                    ball.set_spin(turn * CURVE_SPIN);
+                }
+                Special::Smash => ball.set_boost(SMASH_BOOST),
+                Special::Dash | Special::None => {}
+            }
+        }
+    }
+}
+
+fn spawn_charge_meters(mut commands: Commands, paddle_query: Query<Entity, Added<Paddle>>) {
+    for entity in paddle_query.iter() {
+        let meter = commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::WHITE,
+                        custom_size: Some(Vec2::ZERO),
+                        ..default()
+                    },
+                    ..default()
+                },
+                ChargeMeter,
+            ))
+            .id();
+        commands.entity(entity).add_child(meter);
+    }
+}
+
+// The meter runs along the front of the paddle, as long as the paddle once it's full, and
+// turns yellow while a special is armed or ready
+fn update_charge_meters(
+    paddle_query: Query<(&Paddle, &Children)>,
+    mut meter_query: Query<(&mut Sprite, &mut Transform), With<ChargeMeter>>,
+    arena: Res<ArenaConfig>,
+) {
+    for (paddle, children) in paddle_query.iter() {
+        for child in children.iter() {
+            let Ok((mut sprite, mut transform)) = meter_query.get_mut(*child) else {
+                continue;
+            };
+
+            let ready = paddle.armed || (paddle.charge >= 1.0 && paddle.special != Special::None);
+            let length = if paddle.armed {
+                paddle.length
+            } else {
+                paddle.length * paddle.charge
+            };
+
+            sprite.custom_size = Some(if paddle.team.is_horizontal() {
+                Vec2::new(length, METER_WIDTH)
+            } else {
+                Vec2::new(METER_WIDTH, length)
+            });
+            sprite.color = if ready { Color::YELLOW } else { Color::WHITE };
+            transform.translation =
+                paddle.team.inward() * (arena.paddle_width / 2.0 + METER_WIDTH * 2.0);
+        }
+    }
+}
@@ -0,0 +1,384 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    ascii::StandardOnly,
+    ball::Ball,
+    events::{BallServed, GoalScored, MatchWon, PaddleHit, ScoringSet},
+    files::write_json,
+    four_player::Lives,
+    player::PlayerType,
+    round::RoundPhase,
+    score::Score,
+    settings::{GameMode, Settings, APP_DIRECTORY},
+    theme::Theme,
+};
+
+const MATCHES_DIRECTORY: &str = "matches";
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct PlayerStats {
+    pub points: u32,
+    // Lives left at the end of the match, in the four player modes
+    pub lives: u32,
+    pub hits: u32,
+    // Rallies won by this player when they served
+    pub serves_won: u32,
+    // Seconds in play with this player as the ball's owner
+    pub possession_seconds: f32,
+}
+
+// Collected from the gameplay events over a whole match, shown and exported once it's won
+#[derive(Resource, Serialize, Clone, Debug, Default)]
+pub struct MatchStats {
+    pub mode: GameMode,
+    // The winning team is named after its side player in the teams mode
+    pub winner: Option<PlayerType>,
+    pub player1: PlayerStats,
+    pub player2: PlayerStats,
+    pub player3: PlayerStats,
+    pub player4: PlayerStats,
+    pub rallies: u32,
+    pub longest_rally: u32,
+    pub average_rally: f32,
+    pub fastest_speed: f32,
+    #[serde(skip)]
+    rally_hits: u32,
+    #[serde(skip)]
+    total_rally_hits: u32,
+    #[serde(skip)]
+    server: Option<PlayerType>,
+}
+
+impl MatchStats {
+    pub fn player(&self, player: PlayerType) -> &PlayerStats {
+        match player {
+            PlayerType::Player1 => &self.player1,
+            PlayerType::Player2 => &self.player2,
+            PlayerType::Player3 => &self.player3,
+            PlayerType::Player4 => &self.player4,
+        }
+    }
+
+    fn player_mut(&mut self, player: PlayerType) -> &mut PlayerStats {
+        match player {
+            PlayerType::Player1 => &mut self.player1,
+            PlayerType::Player2 => &mut self.player2,
+            PlayerType::Player3 => &mut self.player3,
+            PlayerType::Player4 => &mut self.player4,
+        }
+    }
+
+    // Share of the time in play each player owned the ball, as a percentage
+    pub fn possession(&self, player: PlayerType) -> f32 {
+        let total: f32 = PlayerType::ALL
+            .iter()
+            .map(|player| self.player(*player).possession_seconds)
+            .sum();
+        if total <= 0.0 {
+            return 0.0;
+        }
+
+        self.player(player).possession_seconds / total * 100.0
+    }
+
+    // The post-match screen, as lines of plain text so every renderer can draw it
+    pub fn summary(&self) -> String {
+        let title = match (self.mode, self.winner) {
+            (GameMode::Teams, Some(PlayerType::Player1)) => "PLAYERS 1 AND 3 WIN",
+            (GameMode::Teams, Some(_)) => "PLAYERS 2 AND 4 WIN",
+            (_, Some(PlayerType::Player1)) => "PLAYER 1 WINS",
+            (_, Some(PlayerType::Player2)) => "PLAYER 2 WINS",
+            (_, Some(PlayerType::Player3)) => "PLAYER 3 WINS",
+            (_, Some(PlayerType::Player4)) => "PLAYER 4 WINS",
+            (_, None) => "MATCH OVER",
+        };
+
+        // A column per player in the match
+        let all = PlayerType::ALL;
+        let players = if self.mode.has_four_paddles() {
+            &all[..]
+        } else {
+            &all[..2]
+        };
+        let row = |label: &str, value: &dyn Fn(PlayerType) -> String| {
+            let columns: String = players
+                .iter()
+                .map(|player| format!("{:>6}", value(*player)))
+                .collect();
+            format!("{label:<14}{columns}")
+        };
+
+        let mut lines = vec![
+            title.to_string(),
+            String::new(),
+            row("", &|player| format!("P{}", player.number())),
+        ];
+        if self.mode.is_four_player() {
+            lines.push(row("Lives", &|player| {
+                self.player(player).lives.to_string()
+            }));
+        } else {
+            lines.push(row("Points", &|player| {
+                self.player(player).points.to_string()
+            }));
+        }
+        lines.extend([
+            row("Hits", &|player| self.player(player).hits.to_string()),
+            row("Serves won", &|player| {
+                self.player(player).serves_won.to_string()
+            }),
+            row("Possession", &|player| {
+                format!("{:.0}%", self.possession(player))
+            }),
+            String::new(),
+            format!("Longest rally {}", self.longest_rally),
+            format!("Average rally {:.1}", self.average_rally),
+            format!("Fastest ball {:.0}", self.fastest_speed),
+            String::new(),
+            "Press Space to continue".to_string(),
+        ]);
+
+        lines.join("\n")
+    }
+}
+
+#[derive(Component)]
+struct SummaryText;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(MatchStats::default())
+            .add_systems(Startup, spawn_summary_text)
+            .add_systems(
+                Update,
+                (
+                    record_stats.after(ScoringSet),
+                    track_possession.run_if(in_state(RoundPhase::Playing)),
+                ),
+            )
+            .add_systems(OnEnter(RoundPhase::MatchOver), show_summary)
+            .add_systems(OnExit(RoundPhase::MatchOver), (hide_summary, reset_stats));
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn record_stats(
+    mut stats: ResMut<MatchStats>,
+    mut served_events: EventReader<BallServed>,
+    mut hit_events: EventReader<PaddleHit>,
+    mut goal_events: EventReader<GoalScored>,
+    mut won_events: EventReader<MatchWon>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    settings: Res<Settings>,
+) {
+    for served in served_events.iter() {
+        stats.server = Some(served.server);
+        stats.rally_hits = 0;
+    }
+
+    for hit in hit_events.iter() {
+        stats.player_mut(hit.player).hits += 1;
+        stats.rally_hits += 1;
+        stats.fastest_speed = stats.fastest_speed.max(hit.speed);
+    }
+
+    for goal in goal_events.iter() {
+        if stats.server == Some(goal.scorer) {
+            stats.player_mut(goal.scorer).serves_won += 1;
+        }
+
+        stats.rallies += 1;
+        stats.total_rally_hits += stats.rally_hits;
+        stats.longest_rally = stats.longest_rally.max(stats.rally_hits);
+        stats.average_rally = stats.total_rally_hits as f32 / stats.rallies as f32;
+        stats.rally_hits = 0;
+        stats.server = None;
+    }
+
+    for won in won_events.iter() {
+        stats.mode = settings.mode;
+        stats.winner = Some(won.winner);
+        for player in PlayerType::ALL {
+            stats.player_mut(player).points = score.points(player);
+            if settings.mode.is_four_player() {
+                stats.player_mut(player).lives = lives.get(player);
+            }
+        }
+
+        export_stats(&stats);
+    }
+}
+
+fn track_possession(mut stats: ResMut<MatchStats>, ball_query: Query<&Ball>, time: Res<Time>) {
+    for ball in ball_query.iter() {
+        if ball.is_fired() {
+            stats.player_mut(ball.owner()).possession_seconds += time.delta_seconds();
+        }
+    }
+}
+
+fn reset_stats(mut stats: ResMut<MatchStats>) {
+    *stats = MatchStats::default();
+}
+
+// Each match gets its own file in the data directory, named after when it finished in
+// milliseconds, e.g. ~/.local/share/bevy-pong/matches/1700000000123.json. A number is added
+// on the end if that name is already taken, so no match overwrites another.
+fn export_path() -> Option<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+
+    let directory = dirs::data_dir()?
+        .join(APP_DIRECTORY)
+        .join(MATCHES_DIRECTORY);
+    let mut path = directory.join(format!("{timestamp}.json"));
+    let mut count = 1;
+    while path.exists() {
+        path = directory.join(format!("{timestamp}-{count}.json"));
+        count += 1;
+    }
+
+    Some(path)
+}
+
+fn export_stats(stats: &MatchStats) {
+    let Some(path) = export_path() else {
+        warn!("No data directory to export match stats to");
+        return;
+    };
+
+    if write_json(&path, stats, "match stats") {
+        info!("Exported match stats to {}", path.display());
+    }
+}
+
+fn spawn_summary_text(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let text_style = TextStyle {
+        font: asset_server.load(theme.font.as_str()),
+        font_size: 28.0,
+        color: theme.text,
+    };
+
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section("", text_style).with_alignment(TextAlignment::Center),
+            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 2.0)),
+            ..default()
+        },
+        SummaryText,
+        StandardOnly,
+    ));
+}
+
+fn show_summary(stats: Res<MatchStats>, mut text_query: Query<&mut Text, With<SummaryText>>) {
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value = stats.summary();
+    }
+}
+
+fn hide_summary(mut text_query: Query<&mut Text, With<SummaryText>>) {
+    for mut text in text_query.iter_mut() {
+        text.sections[0].value.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats_app() -> App {
+        let mut app = App::new();
+        app.add_event::<BallServed>()
+            .add_event::<PaddleHit>()
+            .add_event::<GoalScored>()
+            .add_event::<MatchWon>()
+            .insert_resource(MatchStats::default())
+            .insert_resource(Score::default())
+            .insert_resource(Lives::default())
+            .insert_resource(Settings::default())
+            .add_systems(Update, record_stats);
+        app
+    }
+
+    // Serves the ball, returns it the given number of times and lets the scorer win the point
+    fn play_rally(app: &mut App, server: PlayerType, hits: u32, scorer: PlayerType) {
+        app.world.send_event(BallServed { server });
+        for hit in 0..hits {
+            let player = if hit % 2 == 0 {
+                PlayerType::Player1
+            } else {
+                PlayerType::Player2
+            };
+            app.world.send_event(PaddleHit {
+                player,
+                contact_offset: 0.0,
+                speed: 100.0 + hit as f32,
+                position: Vec3::ZERO,
+            });
+        }
+        let conceded = if scorer == PlayerType::Player1 {
+            PlayerType::Player2
+        } else {
+            PlayerType::Player1
+        };
+        app.world.send_event(GoalScored { scorer, conceded });
+        app.update();
+    }
+
+    #[test]
+    fn rallies_are_counted_by_paddle_hits() {
+        let mut app = stats_app();
+        play_rally(&mut app, PlayerType::Player1, 3, PlayerType::Player1);
+        play_rally(&mut app, PlayerType::Player2, 6, PlayerType::Player2);
+        play_rally(&mut app, PlayerType::Player1, 0, PlayerType::Player2);
+
+        let stats = app.world.resource::<MatchStats>();
+        assert_eq!(stats.rallies, 3);
+        assert_eq!(stats.longest_rally, 6);
+        assert_eq!(stats.average_rally, 3.0);
+        assert_eq!(stats.player1.hits, 5);
+        assert_eq!(stats.player2.hits, 4);
+        assert_eq!(stats.fastest_speed, 105.0);
+    }
+
+    #[test]
+    fn serves_won_only_count_the_server() {
+        let mut app = stats_app();
+        play_rally(&mut app, PlayerType::Player1, 2, PlayerType::Player1);
+        play_rally(&mut app, PlayerType::Player1, 1, PlayerType::Player2);
+        play_rally(&mut app, PlayerType::Player2, 4, PlayerType::Player2);
+        play_rally(&mut app, PlayerType::Player2, 3, PlayerType::Player1);
+
+        let stats = app.world.resource::<MatchStats>();
+        assert_eq!(stats.player1.serves_won, 1);
+        assert_eq!(stats.player2.serves_won, 1);
+    }
+
+    #[test]
+    fn hits_before_a_new_serve_are_not_part_of_its_rally() {
+        let mut app = stats_app();
+        app.world.send_event(PaddleHit {
+            player: PlayerType::Player1,
+            contact_offset: 0.0,
+            speed: 100.0,
+            position: Vec3::ZERO,
+        });
+        app.update();
+        play_rally(&mut app, PlayerType::Player2, 2, PlayerType::Player2);
+
+        let stats = app.world.resource::<MatchStats>();
+        assert_eq!(stats.longest_rally, 2);
+        assert_eq!(stats.player1.hits, 2);
+    }
+}
@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use bevy_kira_audio::prelude::*;
+use serde::Deserialize;
+
+const SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Noise,
+}
+
+// Times are in seconds, sustain is a level between 0.0 and 1.0
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SynthSound {
+    pub waveform: Waveform,
+    pub frequency: f32,
+    // Optional frequency to slide to over the length of the sound
+    #[serde(default)]
+    pub frequency_end: Option<f32>,
+    // Fraction of each square wave period spent high
+    #[serde(default = "default_duty")]
+    pub duty: f32,
+    // How long the note is held before the release starts
+    pub duration: f32,
+    pub envelope: Envelope,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+fn default_duty() -> f32 {
+    0.5
+}
+
+fn default_volume() -> f32 {
+    0.5
+}
+
+impl Envelope {
+    fn level(&self, t: f32, hold: f32) -> f32 {
+        if t < self.attack {
+            return t / self.attack.max(f32::EPSILON);
+        }
+        if t < self.attack + self.decay {
+            let progress = (t - self.attack) / self.decay.max(f32::EPSILON);
+            return 1.0 - (1.0 - self.sustain) * progress;
+        }
+        if t < hold {
+            return self.sustain;
+        }
+
+        let progress = (t - hold) / self.release.max(f32::EPSILON);
+        (self.sustain * (1.0 - progress)).max(0.0)
+    }
+}
+
+pub fn render(sound: &SynthSound) -> StaticSoundData {
+    let hold = sound
+        .duration
+        .max(sound.envelope.attack + sound.envelope.decay);
+    let length = hold + sound.envelope.release;
+    let frame_count = (length * SAMPLE_RATE as f32).ceil() as usize;
+    let frequency_end = sound.frequency_end.unwrap_or(sound.frequency);
+
+    let mut phase = 0.0_f32;
+    let mut noise = NoiseGenerator::new();
+    let mut noise_value = noise.next_value();
+
+    let frames: Vec<Frame> = (0..frame_count)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let frequency = sound.frequency + (frequency_end - sound.frequency) * (t / length);
+
+            let value = match sound.waveform {
+                Waveform::Square => {
+                    if phase < sound.duty {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Waveform::Noise => noise_value,
+            };
+
+            phase += frequency / SAMPLE_RATE as f32;
+            if phase >= 1.0 {
+                phase -= 1.0;
+                // Noise holds each random value for one period, like the NES noise channel
+                noise_value = noise.next_value();
+            }
+
+            let level = sound.envelope.level(t, hold) * sound.volume;
+            Frame::from_mono(value * level)
+        })
+        .collect();
+
+    StaticSoundData {
+        sample_rate: SAMPLE_RATE,
+        frames: Arc::from(frames),
+        settings: StaticSoundSettings::default(),
+    }
+}
+
+// Mixes notes into a loop exactly `length` seconds long, each note starting the given number
+// of seconds in. Anything that rings past the end wraps round to the start so the loop is
+// seamless.
+pub fn render_pattern(notes: &[(f32, SynthSound)], length: f32) -> StaticSoundData {
+    let frame_count = (length * SAMPLE_RATE as f32).round() as usize;
+    let mut frames = vec![Frame::ZERO; frame_count];
+
+    for (start, sound) in notes {
+        let offset = (start * SAMPLE_RATE as f32).round() as usize;
+        for (i, frame) in render(sound).frames.iter().enumerate() {
+            frames[(offset + i) % frame_count] += *frame;
+        }
+    }
+
+    StaticSoundData {
+        sample_rate: SAMPLE_RATE,
+        frames: Arc::from(frames),
+        settings: StaticSoundSettings::default(),
+    }
+}
+
+// Small xorshift generator so the noise channel doesn't need an rng dependency
+struct NoiseGenerator {
+    state: u32,
+}
+
+impl NoiseGenerator {
+    fn new() -> Self {
+        NoiseGenerator { state: 0x1234_5678 }
+    }
+
+    fn next_value(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+
+        self.state as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::texture::DEFAULT_IMAGE_HANDLE,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    arena::ArenaConfig,
+    ascii::{SpriteStyleSet, StandardOnly},
+    ball::Ball,
+    files::read_asset_ron,
+    player::{Paddle, PlayerType},
+};
+
+// In the assets folder. The asset server picks a loader by everything after a dot in the
+// file name, so the name needs a part before `.themes.ron` to reach the themes loader.
+const THEMES_PATH: &str = "default.themes.ron";
+
+// Colours, font and optional textures everything in the arena is drawn with. Colours are
+// written as hex strings in the themes file, e.g. "#ffffff".
+#[derive(Resource, Deserialize, Clone, Debug)]
+pub struct Theme {
+    #[serde(deserialize_with = "hex_colour")]
+    pub background: Color,
+    #[serde(deserialize_with = "hex_colour")]
+    pub paddle: Color,
+    #[serde(deserialize_with = "hex_colour")]
+    pub ball: Color,
+    // The centre line and the walls
+    #[serde(deserialize_with = "hex_colour")]
+    pub lines: Color,
+    #[serde(deserialize_with = "hex_colour")]
+    pub text: Color,
+    // Paddle and score colour of each player in order, players without one use `paddle`
+    // and `text`
+    #[serde(default, deserialize_with = "hex_colours")]
+    pub players: Vec<Color>,
+    // Path in the assets folder
+    pub font: String,
+    // Paths in the assets folder, drawn tinted by the colours above
+    #[serde(default)]
+    pub paddle_texture: Option<String>,
+    #[serde(default)]
+    pub ball_texture: Option<String>,
+    // Drawn over the whole arena, behind everything else
+    #[serde(default)]
+    pub arena_texture: Option<String>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: Color::BLACK,
+            paddle: Color::WHITE,
+            ball: Color::WHITE,
+            lines: Color::WHITE,
+            text: Color::WHITE,
+            players: Vec::new(),
+            font: "fonts/Minecraft.ttf".into(),
+            paddle_texture: None,
+            ball_texture: None,
+            arena_texture: None,
+        }
+    }
+}
+
+impl Theme {
+    pub fn player_colour(&self, player: PlayerType) -> Option<Color> {
+        self.players.get(player.number() as usize - 1).copied()
+    }
+}
+
+fn hex_colour<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color::hex(hex.trim_start_matches('#')).map_err(serde::de::Error::custom)
+}
+
+fn hex_colours<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|hex| Color::hex(hex.trim_start_matches('#')).map_err(serde::de::Error::custom))
+        .collect()
+}
+
+// Every theme in the themes file. It's loaded as an asset, so the asset server reloads it
+// whenever the file is saved while the game runs.
+#[derive(Deserialize, TypeUuid, TypePath)]
+#[uuid = "3c8e57a2-5d0b-4f1e-9a6c-2b7d91e4f063"]
+struct Themes {
+    default: String,
+    themes: HashMap<String, Theme>,
+}
+
+impl Themes {
+    // The named theme, or the file's default when there's no name
+    fn pick(&self, name: Option<&String>) -> Theme {
+        let name = name.unwrap_or(&self.default);
+        self.themes.get(name).cloned().unwrap_or_else(|| {
+            warn!("No theme named {name:?} in {THEMES_PATH}, using the classic theme");
+            Theme::default()
+        })
+    }
+}
+
+#[derive(Default)]
+struct ThemesLoader;
+
+impl AssetLoader for ThemesLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let themes = ron::de::from_bytes::<Themes>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(themes));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["themes.ron"]
+    }
+}
+
+// Checks a theme chosen in the settings is in the themes file, the error lists the ones that
+// are. Any name is let through when the file can't be read, the classic theme is used then.
+pub fn check_theme_name(name: &str) -> Result<(), String> {
+    let Ok(file) = read_asset_ron::<Themes>(THEMES_PATH) else {
+        return Ok(());
+    };
+    if file.themes.contains_key(name) {
+        return Ok(());
+    }
+
+    let mut names: Vec<_> = file.themes.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    Err(format!(
+        "no theme named {name:?} in {THEMES_PATH}, choose from {}",
+        names.join(", ")
+    ))
+}
+
+// Sprites that are drawn in the theme's line colour
+#[derive(Component)]
+pub struct ThemeLine;
+
+#[derive(Component)]
+struct ArenaArt;
+
+// The theme being played with and the themes file it comes from
+#[derive(Resource)]
+struct ThemeSource {
+    name: Option<String>,
+    themes: Handle<Themes>,
+}
+
+// Everything is drawn in the classic theme until the themes file has loaded, usually only
+// for the first frame
+pub struct ThemePlugin {
+    // Name of the theme to use, the file's default is used when this is None
+    pub theme: Option<String>,
+}
+
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<Themes>()
+            .init_asset_loader::<ThemesLoader>();
+
+        let themes = app.world.resource::<AssetServer>().load(THEMES_PATH);
+        let theme = Theme::default();
+
+        app.insert_resource(ClearColor(theme.background))
+            .insert_resource(theme)
+            .insert_resource(ThemeSource {
+                name: self.theme.clone(),
+                themes,
+            })
+            .add_systems(
+                Update,
+                (
+                    reload_theme,
+                    apply_theme.in_set(SpriteStyleSet),
+                    apply_arena_art,
+                )
+                    .chain(),
+            );
+    }
+}
+
+// Picks the theme out of the themes file once it has loaded, and again whenever it's saved
+fn reload_theme(
+    mut events: EventReader<AssetEvent<Themes>>,
+    themes: Res<Assets<Themes>>,
+    source: Res<ThemeSource>,
+    mut theme: ResMut<Theme>,
+    mut clear_colour: ResMut<ClearColor>,
+) {
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } => handle,
+            AssetEvent::Modified { handle } => {
+                info!("{THEMES_PATH} changed, reloading the theme");
+                handle
+            }
+            AssetEvent::Removed { .. } => continue,
+        };
+        let Some(file) = themes.get(handle).filter(|_| *handle == source.themes) else {
+            continue;
+        };
+
+        *theme = file.pick(source.name.as_ref());
+        clear_colour.0 = theme.background;
+    }
+}
+
+// Keeps the alpha, the ASCII display mode hides sprites by making them transparent
+fn recolour(colour: &mut Color, theme_colour: Color) {
+    let alpha = colour.a();
+    *colour = theme_colour.with_a(alpha);
+}
+
+// Colours everything when the theme changes, and anything new as it's spawned
+#[allow(clippy::type_complexity)]
+fn apply_theme(
+    mut paddle_query: Query<(Ref<Paddle>, &mut Sprite, &mut Handle<Image>), Without<Ball>>,
+    mut ball_query: Query<(Ref<Ball>, &mut Sprite, &mut Handle<Image>), Without<Paddle>>,
+    mut line_query: Query<(Ref<ThemeLine>, &mut Sprite), (Without<Paddle>, Without<Ball>)>,
+    mut text_query: Query<(&mut Text, Option<&PlayerType>)>,
+    theme: Res<Theme>,
+    asset_server: Res<AssetServer>,
+) {
+    let changed = theme.is_changed();
+    let texture = |path: &Option<String>| match path {
+        Some(path) => asset_server.load(path.as_str()),
+        None => DEFAULT_IMAGE_HANDLE.typed(),
+    };
+
+    for (paddle, mut sprite, mut image) in paddle_query.iter_mut() {
+        if changed || paddle.is_added() {
+            let colour = theme
+                .player_colour(paddle.player_type)
+                .unwrap_or(theme.paddle);
+            recolour(&mut sprite.color, colour);
+            *image = texture(&theme.paddle_texture);
+        }
+    }
+    for (ball, mut sprite, mut image) in ball_query.iter_mut() {
+        if changed || ball.is_added() {
+            recolour(&mut sprite.color, theme.ball);
+            *image = texture(&theme.ball_texture);
+        }
+    }
+    for (line, mut sprite) in line_query.iter_mut() {
+        if changed || line.is_added() {
+            recolour(&mut sprite.color, theme.lines);
+        }
+    }
+    for (mut text, player) in text_query.iter_mut() {
+        if !changed && !text.is_added() {
+            continue;
+        }
+
+        // Scores are in their player's colour
+        let colour = player
+            .and_then(|player| theme.player_colour(*player))
+            .unwrap_or(theme.text);
+        for section in text.sections.iter_mut() {
+            recolour(&mut section.style.color, colour);
+            section.style.font = asset_server.load(theme.font.as_str());
+        }
+    }
+}
+
+// Draws the theme's arena texture behind everything, or takes it away when there isn't one
+fn apply_arena_art(
+    mut commands: Commands,
+    art_query: Query<Entity, With<ArenaArt>>,
+    theme: Res<Theme>,
+    arena: Res<ArenaConfig>,
+    asset_server: Res<AssetServer>,
+) {
+    if !theme.is_changed() {
+        return;
+    }
+
+    for entity in art_query.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(path) = &theme.arena_texture else {
+        return;
+    };
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(Vec2::new(arena.width, arena.height)),
+                ..default()
+            },
+            texture: asset_server.load(path.as_str()),
+            transform: Transform::from_xyz(0.0, 0.0, -10.0),
+            ..default()
+        },
+        ArenaArt,
+        StandardOnly,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    // Loads the real themes file through the asset server the way the game does, and
+    // returns the theme it ends up drawn with
+    fn loaded_theme(name: &str) -> Theme {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .insert_resource(ArenaConfig::default())
+            .add_plugins(ThemePlugin {
+                theme: Some(name.into()),
+            });
+
+        let handle = app.world.resource::<ThemeSource>().themes.clone();
+        for _ in 0..500 {
+            app.update();
+            if app.world.resource::<Assets<Themes>>().contains(&handle) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        // The theme is picked on the frame after the file is added
+        app.update();
+
+        app.world.resource::<Theme>().clone()
+    }
+
+    #[test]
+    fn chosen_preset_is_applied() {
+        let theme = loaded_theme("high-contrast");
+
+        assert_eq!(theme.ball, Color::hex("ffff00").unwrap());
+    }
+
+    #[test]
+    fn unknown_theme_names_are_rejected() {
+        assert!(check_theme_name("colour-blind").is_ok());
+
+        let error = check_theme_name("sepia").unwrap_err();
+        assert!(error.contains("arcade, classic, colour-blind, high-contrast"));
+    }
+}
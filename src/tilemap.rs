@@ -1,6 +1,11 @@
 use bevy::prelude::*;
 
-use crate::{ARENA_HEIGHT, ARENA_WIDTH};
+use crate::{
+    arena::{ArenaConfig, Walls},
+    ascii::{AsciiArt, GLYPH_DOUBLE_HORIZONTAL_LINE, GLYPH_VERTICAL_LINE},
+    player::PlayerType,
+    theme::{Theme, ThemeLine},
+};
 
 const DASH_HEIGHT: f32 = 20.0;
 const DASH_WIDTH: f32 = 5.0;
@@ -8,51 +13,113 @@ const DASH_SPACING: f32 = 20.0;
 
 pub struct TilemapPlugin;
 
+// The line along the side of the arena guarded by a player, only shown while it's a wall
+#[derive(Component)]
+struct WallLine(PlayerType);
+
 impl Plugin for TilemapPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (draw_dashed_line, draw_arena_outline));
+        app.add_systems(Startup, (draw_dashed_line, draw_arena_outline))
+            .add_systems(Update, show_walls);
     }
 }
 
-fn draw_arena_outline(mut commands: Commands) {
-    let top_position = -ARENA_HEIGHT / 2.0;
-    let bottom_position = ARENA_HEIGHT / 2.0;
+fn draw_arena_outline(mut commands: Commands, arena: Res<ArenaConfig>, theme: Res<Theme>) {
+    let top_position = -arena.height / 2.0;
+    let bottom_position = arena.height / 2.0;
 
-    commands.spawn(SpriteBundle {
-        sprite: Sprite {
-            color: Color::WHITE,
-            custom_size: Some(Vec2::new(ARENA_WIDTH * 3.0, 2.0)),
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: theme.lines,
+                custom_size: Some(Vec2::new(arena.width * 3.0, 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(-arena.width / 2.0, top_position, 0.0),
             ..default()
         },
-        transform: Transform::from_xyz(-ARENA_WIDTH / 2.0, top_position, 0.0),
-        ..default()
-    });
-
-    commands.spawn(SpriteBundle {
-        sprite: Sprite {
-            color: Color::WHITE,
-            custom_size: Some(Vec2::new(ARENA_WIDTH * 3.0, 2.0)),
+        AsciiArt {
+            glyph: GLYPH_DOUBLE_HORIZONTAL_LINE,
+        },
+        WallLine(PlayerType::Player4),
+        ThemeLine,
+    ));
+
+    commands.spawn((
+        SpriteBundle {
+            sprite: Sprite {
+                color: theme.lines,
+                custom_size: Some(Vec2::new(arena.width * 3.0, 2.0)),
+                ..default()
+            },
+            transform: Transform::from_xyz(-arena.width / 2.0, bottom_position, 0.0),
             ..default()
         },
-        transform: Transform::from_xyz(-ARENA_WIDTH / 2.0, bottom_position, 0.0),
-        ..default()
-    });
+        AsciiArt {
+            glyph: GLYPH_DOUBLE_HORIZONTAL_LINE,
+        },
+        WallLine(PlayerType::Player3),
+        ThemeLine,
+    ));
+
+    // The sides only become walls in the four player modes, once their player is out
+    for (player, x) in [
+        (PlayerType::Player1, -arena.width / 2.0),
+        (PlayerType::Player2, arena.width / 2.0),
+    ] {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.lines,
+                    custom_size: Some(Vec2::new(2.0, arena.height)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(x, 0.0, 0.0),
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            AsciiArt {
+                glyph: GLYPH_VERTICAL_LINE,
+            },
+            WallLine(player),
+            ThemeLine,
+        ));
+    }
+}
 
+fn show_walls(walls: Res<Walls>, mut line_query: Query<(&WallLine, &mut Visibility)>) {
+    if !walls.is_changed() {
+        return;
+    }
+
+    for (line, mut visibility) in line_query.iter_mut() {
+        *visibility = if walls.is_wall(line.0) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
 }
 
-fn draw_dashed_line(mut commands: Commands) {
-    let mut position_y = -ARENA_HEIGHT / 2.0 + 20.0;
+fn draw_dashed_line(mut commands: Commands, arena: Res<ArenaConfig>, theme: Res<Theme>) {
+    let mut position_y = -arena.height / 2.0 + 20.0;
 
-    while position_y < ARENA_HEIGHT / 2.0 {
-        commands.spawn(SpriteBundle {
-            sprite: Sprite {
-                color: Color::WHITE,
-                custom_size: Some(Vec2::new(DASH_WIDTH, DASH_HEIGHT)),
+    while position_y < arena.height / 2.0 {
+        commands.spawn((
+            SpriteBundle {
+                sprite: Sprite {
+                    color: theme.lines,
+                    custom_size: Some(Vec2::new(DASH_WIDTH, DASH_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, position_y, 0.0),
                 ..default()
             },
-            transform: Transform::from_xyz(0.0, position_y, 0.0),
-            ..default()
-        });
+            AsciiArt {
+                glyph: GLYPH_VERTICAL_LINE,
+            },
+            ThemeLine,
+        ));
 
         position_y += DASH_HEIGHT + DASH_SPACING;
     }
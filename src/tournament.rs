@@ -0,0 +1,527 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::MatchWon,
+    files::write_json,
+    player::PlayerType,
+    profiles::PlayerNames,
+    settings::{AiDifficulty, GameMode, Settings, APP_DIRECTORY},
+};
+
+const TOURNAMENT_FILE: &str = "tournament.json";
+
+pub const MIN_TOURNAMENT_PLAYERS: usize = 4;
+pub const MAX_TOURNAMENT_PLAYERS: usize = 32;
+// More than this and the bracket screen only shows the rounds still to be played
+const MAX_BRACKET_LINES: usize = 16;
+
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Elimination {
+    #[default]
+    Single,
+    // Players drop into a losers bracket after their first defeat, and out after their second
+    Double,
+}
+
+// Where a player in a bracket match comes from
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    Player(usize),
+    Bye,
+    WinnerOf(usize),
+    LoserOf(usize),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Entrant {
+    Player(usize),
+    Bye,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bracket {
+    Winners,
+    Losers,
+    GrandFinal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BracketMatch {
+    pub bracket: Bracket,
+    pub round: u32,
+    pub sides: [Slot; 2],
+    // Index into `sides` of the winner, once the match has been played
+    pub winner: Option<usize>,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct Tournament {
+    pub players: Vec<String>,
+    pub elimination: Elimination,
+    pub matches: Vec<BracketMatch>,
+    // The match being played right now, if any
+    #[serde(skip)]
+    current: Option<usize>,
+    // Set when a match finishes, so the menu opens on the bracket afterwards
+    #[serde(skip)]
+    finished: bool,
+}
+
+impl Tournament {
+    pub fn new(players: Vec<String>, elimination: Elimination) -> Tournament {
+        let mut tournament = Tournament {
+            players,
+            elimination,
+            matches: Vec::new(),
+            current: None,
+            finished: false,
+        };
+
+        let winners = tournament.add_winners_bracket();
+        if elimination == Elimination::Double {
+            let losers_final = tournament.add_losers_bracket(&winners);
+            let winners_final = winners[winners.len() - 1][0];
+
+            tournament.matches.push(BracketMatch {
+                bracket: Bracket::GrandFinal,
+                round: 1,
+                sides: [Slot::WinnerOf(winners_final), Slot::WinnerOf(losers_final)],
+                winner: None,
+            });
+        }
+
+        tournament
+    }
+
+    // Players are seeded in the order they were entered, with the byes going to the top seeds
+    fn add_winners_bracket(&mut self) -> Vec<Vec<usize>> {
+        let size = self.players.len().next_power_of_two();
+        let seeds = seeding(size);
+        let mut rounds: Vec<Vec<usize>> = Vec::new();
+
+        let mut slots: Vec<Slot> = seeds
+            .iter()
+            .map(|&seed| {
+                if seed < self.players.len() {
+                    Slot::Player(seed)
+                } else {
+                    Slot::Bye
+                }
+            })
+            .collect();
+
+        while slots.len() > 1 {
+            let round = self.add_round(Bracket::Winners, rounds.len() as u32 + 1, &slots);
+            slots = round.iter().map(|&index| Slot::WinnerOf(index)).collect();
+            rounds.push(round);
+        }
+
+        rounds
+    }
+
+    // The losers of each winners round drop in to play the survivors of the losers bracket.
+    // Returns the losers final.
+    fn add_losers_bracket(&mut self, winners: &[Vec<usize>]) -> usize {
+        let first_losers: Vec<Slot> = winners[0]
+            .iter()
+            .map(|&index| Slot::LoserOf(index))
+            .collect();
+        let mut survivors = self.add_round(Bracket::Losers, 1, &first_losers);
+        let mut round = 1;
+
+        for (winners_round, dropping) in winners.iter().enumerate().skip(1) {
+            // Alternate the order the losers drop in, so players don't meet again straight away
+            let mut dropping: Vec<usize> = dropping.clone();
+            if winners_round % 2 == 1 {
+                dropping.reverse();
+            }
+
+            round += 1;
+            let slots: Vec<Slot> = survivors
+                .iter()
+                .zip(dropping.iter())
+                .flat_map(|(&survivor, &dropped)| {
+                    [Slot::WinnerOf(survivor), Slot::LoserOf(dropped)]
+                })
+                .collect();
+            survivors = self.add_round(Bracket::Losers, round, &slots);
+
+            if survivors.len() > 1 {
+                round += 1;
+                let slots: Vec<Slot> = survivors
+                    .iter()
+                    .map(|&index| Slot::WinnerOf(index))
+                    .collect();
+                survivors = self.add_round(Bracket::Losers, round, &slots);
+            }
+        }
+
+        survivors[0]
+    }
+
+    fn add_round(&mut self, bracket: Bracket, round: u32, slots: &[Slot]) -> Vec<usize> {
+        slots
+            .chunks(2)
+            .map(|pair| {
+                self.matches.push(BracketMatch {
+                    bracket,
+                    round,
+                    sides: [pair[0], pair[1]],
+                    winner: None,
+                });
+                self.matches.len() - 1
+            })
+            .collect()
+    }
+
+    // Who ends up in a slot, or None while the match it depends on hasn't been played
+    pub fn resolve(&self, slot: Slot) -> Option<Entrant> {
+        match slot {
+            Slot::Player(index) => Some(Entrant::Player(index)),
+            Slot::Bye => Some(Entrant::Bye),
+            Slot::WinnerOf(index) => self.outcome(index).map(|(winner, _)| winner),
+            Slot::LoserOf(index) => self.outcome(index).map(|(_, loser)| loser),
+        }
+    }
+
+    // The winner and loser of a match. Matches against a bye are won without being played.
+    pub fn outcome(&self, index: usize) -> Option<(Entrant, Entrant)> {
+        let bracket_match = &self.matches[index];
+        let sides = [
+            self.resolve(bracket_match.sides[0])?,
+            self.resolve(bracket_match.sides[1])?,
+        ];
+
+        match (sides, bracket_match.winner) {
+            ([Entrant::Bye, other], _) | ([other, Entrant::Bye], _) => Some((other, Entrant::Bye)),
+            (_, Some(winner)) => Some((sides[winner], sides[1 - winner])),
+            (_, None) => None,
+        }
+    }
+
+    // The first match with both players known that hasn't been played yet
+    pub fn next_match(&self) -> Option<usize> {
+        (0..self.matches.len()).find(|&index| {
+            self.outcome(index).is_none()
+                && self.matches[index]
+                    .sides
+                    .iter()
+                    .all(|&slot| matches!(self.resolve(slot), Some(Entrant::Player(_))))
+        })
+    }
+
+    pub fn players_in(&self, index: usize) -> Option<(usize, usize)> {
+        let sides = self.matches[index].sides;
+
+        match (self.resolve(sides[0]), self.resolve(sides[1])) {
+            (Some(Entrant::Player(player1)), Some(Entrant::Player(player2))) => {
+                Some((player1, player2))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn champion(&self) -> Option<&str> {
+        match self.outcome(self.matches.len() - 1) {
+            Some((Entrant::Player(index), _)) => Some(&self.players[index]),
+            _ => None,
+        }
+    }
+
+    // Remembers which match is being played, and returns its players' names
+    pub fn start_match(&mut self, index: usize) -> Option<PlayerNames> {
+        let (player1, player2) = self.players_in(index)?;
+        self.current = Some(index);
+
+        Some(PlayerNames {
+            player1: self.players[player1].clone(),
+            player2: self.players[player2].clone(),
+        })
+    }
+
+    fn finish_match(&mut self, winner: PlayerType) {
+        let Some(index) = self.current.take() else {
+            return;
+        };
+
+        self.matches[index].winner = Some(match winner {
+            PlayerType::Player1 => 0,
+            PlayerType::Player2 => 1,
+            // Tournaments are only played in the classic mode
+            PlayerType::Player3 | PlayerType::Player4 => return,
+        });
+        self.finished = true;
+    }
+
+    // Whether a match has finished since the last time this was asked
+    pub fn take_finished(&mut self) -> bool {
+        std::mem::take(&mut self.finished)
+    }
+
+    fn entrant_name(&self, slot: Slot) -> &str {
+        match self.resolve(slot) {
+            Some(Entrant::Player(index)) => &self.players[index],
+            Some(Entrant::Bye) => "bye",
+            None => "TBD",
+        }
+    }
+
+    // The bracket screen shown between matches, as lines of plain text
+    pub fn bracket_text(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut next_line = 0;
+        let next_match = self.next_match();
+        let mut heading = None;
+
+        for (index, bracket_match) in self.matches.iter().enumerate() {
+            let sides = bracket_match.sides.map(|slot| self.resolve(slot));
+            // Byes aren't worth showing
+            if sides.contains(&Some(Entrant::Bye)) {
+                continue;
+            }
+
+            let round_heading = match bracket_match.bracket {
+                Bracket::Winners if self.elimination == Elimination::Single => {
+                    format!("Round {}", bracket_match.round)
+                }
+                Bracket::Winners => format!("Winners round {}", bracket_match.round),
+                Bracket::Losers => format!("Losers round {}", bracket_match.round),
+                Bracket::GrandFinal => "Grand final".to_string(),
+            };
+            if heading.as_ref() != Some(&round_heading) {
+                if Some(index) <= next_match || next_match.is_none() {
+                    next_line = lines.len();
+                }
+                lines.push(round_heading.clone());
+                heading = Some(round_heading);
+            }
+
+            let player1 = self.entrant_name(bracket_match.sides[0]);
+            let player2 = self.entrant_name(bracket_match.sides[1]);
+            lines.push(match bracket_match.winner {
+                Some(0) => format!("  {player1:>12} def. {player2}"),
+                Some(_) => format!("  {player2:>12} def. {player1}"),
+                None => format!("  {player1:>12}  vs  {player2}"),
+            });
+        }
+
+        // Keep the round with the next match in view on big brackets
+        if lines.len() > MAX_BRACKET_LINES {
+            let start = next_line.min(lines.len() - MAX_BRACKET_LINES);
+            lines = lines[start..start + MAX_BRACKET_LINES].to_vec();
+        }
+
+        let title = match self.elimination {
+            Elimination::Single => "TOURNAMENT",
+            Elimination::Double => "TOURNAMENT (double elimination)",
+        };
+        let footer = match (
+            self.champion(),
+            next_match.and_then(|index| self.players_in(index)),
+        ) {
+            (Some(champion), _) => format!("{champion} is the champion!"),
+            (None, Some((player1, player2))) => format!(
+                "Next: {} vs {}",
+                self.players[player1], self.players[player2]
+            ),
+            (None, None) => String::new(),
+        };
+
+        [
+            vec![title.to_string(), String::new()],
+            lines,
+            vec![String::new(), footer],
+        ]
+        .concat()
+        .join("\n")
+    }
+
+    // e.g. ~/.local/share/bevy-pong/tournament.json
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(APP_DIRECTORY).join(TOURNAMENT_FILE))
+    }
+
+    pub fn load() -> Option<Tournament> {
+        let path = Tournament::path()?;
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|err| warn!("Failed to parse {}: {err}", path.display()))
+                .ok(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                warn!("Failed to read {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Tournament::path() else {
+            warn!("No data directory to save the tournament to");
+            return;
+        };
+
+        write_json(&path, self, "the tournament");
+    }
+}
+
+// Bracket positions for each seed, so the top seeds can only meet in the later rounds,
+// e.g. [0, 3, 1, 2] for four players
+fn seeding(size: usize) -> Vec<usize> {
+    let mut seeds = vec![0];
+
+    while seeds.len() < size {
+        let count = seeds.len() * 2;
+        seeds = seeds
+            .iter()
+            .flat_map(|&seed| [seed, count - 1 - seed])
+            .collect();
+    }
+
+    seeds
+}
+
+pub struct TournamentPlugin;
+
+impl Plugin for TournamentPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PreStartup, start_tournament)
+            .add_systems(Update, record_tournament_result);
+    }
+}
+
+// A new tournament is started when players are given, otherwise a saved one carries on
+// Tournament matches are one against one between two people, so there is none outside the
+// classic mode or against the computer
+fn start_tournament(mut commands: Commands, settings: Res<Settings>) {
+    if settings.mode != GameMode::Classic || settings.ai_difficulty != AiDifficulty::Off {
+        return;
+    }
+
+    let tournament = if settings.tournament_players.is_empty() {
+        Tournament::load()
+    } else {
+        let tournament = Tournament::new(settings.tournament_players.clone(), settings.elimination);
+        tournament.save();
+        Some(tournament)
+    };
+
+    if let Some(tournament) = tournament {
+        commands.insert_resource(tournament);
+    }
+}
+
+fn record_tournament_result(
+    mut won_events: EventReader<MatchWon>,
+    tournament: Option<ResMut<Tournament>>,
+) {
+    let Some(mut tournament) = tournament else {
+        won_events.clear();
+        return;
+    };
+
+    for won in won_events.iter() {
+        if tournament.current.is_some() {
+            tournament.finish_match(won.winner);
+            tournament.save();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_tournament(count: usize, elimination: Elimination) -> Tournament {
+        let players = (1..=count).map(|number| format!("P{number}")).collect();
+        Tournament::new(players, elimination)
+    }
+
+    // Plays the next match, won by whoever is on the given side, and returns its index
+    fn play_next(tournament: &mut Tournament, winner: PlayerType) -> usize {
+        let index = tournament.next_match().expect("a match to play");
+        tournament.start_match(index);
+        tournament.finish_match(winner);
+        index
+    }
+
+    #[test]
+    fn byes_go_to_the_top_seeds() {
+        let tournament = new_tournament(5, Elimination::Single);
+
+        // Seeds 1, 2 and 3 get byes, so only seeds 4 and 5 play in the first round
+        assert_eq!(
+            tournament.outcome(0),
+            Some((Entrant::Player(0), Entrant::Bye))
+        );
+        assert_eq!(tournament.next_match(), Some(1));
+        assert_eq!(tournament.players_in(1), Some((3, 4)));
+        assert_eq!(tournament.bracket_text().matches(" bye").count(), 0);
+    }
+
+    #[test]
+    fn single_elimination_crowns_the_last_winner() {
+        let mut tournament = new_tournament(4, Elimination::Single);
+
+        assert_eq!(play_next(&mut tournament, PlayerType::Player1), 0);
+        assert_eq!(play_next(&mut tournament, PlayerType::Player2), 1);
+        assert_eq!(tournament.players_in(2), Some((0, 2)));
+        assert_eq!(play_next(&mut tournament, PlayerType::Player2), 2);
+
+        assert_eq!(tournament.champion(), Some("P3"));
+        assert_eq!(tournament.next_match(), None);
+    }
+
+    #[test]
+    fn losers_drop_into_the_losers_bracket() {
+        let mut tournament = new_tournament(4, Elimination::Double);
+
+        // P1 beats P4 and P2 beats P3, so P4 and P3 meet in the losers bracket
+        play_next(&mut tournament, PlayerType::Player1);
+        play_next(&mut tournament, PlayerType::Player1);
+        assert_eq!(tournament.matches[3].bracket, Bracket::Losers);
+        assert_eq!(tournament.players_in(3), Some((3, 2)));
+
+        // The winners final is played first, its loser P2 drops down to face P4
+        assert_eq!(play_next(&mut tournament, PlayerType::Player1), 2);
+        assert_eq!(play_next(&mut tournament, PlayerType::Player1), 3);
+        assert_eq!(tournament.matches[4].bracket, Bracket::Losers);
+        assert_eq!(tournament.players_in(4), Some((3, 1)));
+        assert_eq!(tournament.champion(), None);
+    }
+
+    #[test]
+    fn grand_final_is_between_the_two_bracket_winners() {
+        let mut tournament = new_tournament(4, Elimination::Double);
+
+        for _ in 0..5 {
+            play_next(&mut tournament, PlayerType::Player1);
+        }
+
+        let grand_final = tournament.matches.len() - 1;
+        assert_eq!(tournament.matches[grand_final].bracket, Bracket::GrandFinal);
+        assert_eq!(tournament.next_match(), Some(grand_final));
+        assert_eq!(tournament.players_in(grand_final), Some((0, 3)));
+
+        // The losers bracket winner can still take the title
+        play_next(&mut tournament, PlayerType::Player2);
+        assert_eq!(tournament.champion(), Some("P4"));
+        assert_eq!(tournament.next_match(), None);
+    }
+
+    #[test]
+    fn finished_match_is_reported_once() {
+        let mut tournament = new_tournament(4, Elimination::Single);
+        assert!(!tournament.take_finished());
+
+        play_next(&mut tournament, PlayerType::Player1);
+        assert!(tournament.take_finished());
+        assert!(!tournament.take_finished());
+    }
+}
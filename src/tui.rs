@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use bevy::{app::AppExit, input::InputSystem, prelude::*};
+use crossterm::{
+    cursor,
+    event::{
+        self, Event, KeyCode as TermKeyCode, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+
+use crate::{
+    arena::{ArenaConfig, Walls},
+    ball::Ball,
+    four_player::Lives,
+    menu::MenuText,
+    player::{Paddle, PlayerType},
+    round::{Countdown, RoundPhase},
+    score::Score,
+    settings::{GameMode, Settings},
+    specials::meter_label,
+    stats::MatchStats,
+};
+
+// Terminals without key release events only repeat held keys, so a key counts as held
+// until it hasn't been repeated for this long
+const HOLD_SECONDS: f32 = 0.2;
+const MAX_COLUMNS: u16 = 120;
+const MAX_ROWS: u16 = 40;
+const MIN_COLUMNS: u16 = 20;
+const MIN_ROWS: u16 = 8;
+
+const WALL: char = '#';
+const NET: char = '.';
+const PADDLE: char = '|';
+const FLAT_PADDLE: char = '=';
+const BALL: char = 'O';
+
+pub struct TuiPlugin {
+    // Whether the terminal reports key releases, see `enter_terminal`
+    pub enhanced_keys: bool,
+}
+
+#[derive(Resource)]
+struct TerminalKeys {
+    enhanced: bool,
+    held: HashMap<KeyCode, f32>,
+}
+
+impl Plugin for TuiPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(TerminalKeys {
+            enhanced: self.enhanced_keys,
+            held: HashMap::new(),
+        })
+        .add_systems(PreUpdate, read_terminal_input.after(InputSystem))
+        .add_systems(Last, draw_terminal);
+    }
+}
+
+// Switches the terminal to raw mode on the alternate screen. Returns true if the
+// terminal supports reporting key releases.
+pub fn enter_terminal() -> io::Result<bool> {
+    terminal::enable_raw_mode()?;
+    execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+
+    let enhanced_keys = terminal::supports_keyboard_enhancement().unwrap_or(false);
+    if enhanced_keys {
+        execute!(
+            io::stdout(),
+            PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+        )?;
+    }
+
+    // Put the terminal back if the game panics, otherwise the shell is left in raw mode
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = leave_terminal(enhanced_keys);
+        default_hook(info);
+    }));
+
+    Ok(enhanced_keys)
+}
+
+pub fn leave_terminal(enhanced_keys: bool) -> io::Result<()> {
+    if enhanced_keys {
+        execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()
+}
+
+fn read_terminal_input(
+    mut keys: ResMut<TerminalKeys>,
+    mut keyboard: ResMut<Input<KeyCode>>,
+    mut exit: EventWriter<AppExit>,
+    time: Res<Time>,
+) {
+    if !keys.enhanced {
+        let delta = time.delta_seconds();
+
+        keys.held.retain(|key_code, remaining| {
+            *remaining -= delta;
+            if *remaining <= 0.0 {
+                keyboard.release(*key_code);
+            }
+            *remaining > 0.0
+        });
+    }
+
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+
+        let ctrl_c =
+            key.code == TermKeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl_c || key.code == TermKeyCode::Char('q') || key.code == TermKeyCode::Esc {
+            exit.send(AppExit);
+            return;
+        }
+
+        let Some(key_code) = map_key(key.code) else {
+            continue;
+        };
+
+        match key.kind {
+            KeyEventKind::Press | KeyEventKind::Repeat => {
+                keyboard.press(key_code);
+                keys.held.insert(key_code, HOLD_SECONDS);
+            }
+            KeyEventKind::Release => {
+                keyboard.release(key_code);
+                keys.held.remove(&key_code);
+            }
+        }
+    }
+}
+
+fn map_key(code: TermKeyCode) -> Option<KeyCode> {
+    match code {
+        TermKeyCode::Char('w') | TermKeyCode::Char('W') => Some(KeyCode::W),
+        TermKeyCode::Char('s') | TermKeyCode::Char('S') => Some(KeyCode::S),
+        TermKeyCode::Char(' ') => Some(KeyCode::Space),
+        TermKeyCode::Enter => Some(KeyCode::Return),
+        TermKeyCode::Char('i') | TermKeyCode::Char('I') => Some(KeyCode::I),
+        TermKeyCode::Char('j') | TermKeyCode::Char('J') => Some(KeyCode::J),
+        TermKeyCode::Char('k') | TermKeyCode::Char('K') => Some(KeyCode::K),
+        TermKeyCode::Char('l') | TermKeyCode::Char('L') => Some(KeyCode::L),
+        TermKeyCode::Char('d') | TermKeyCode::Char('D') => Some(KeyCode::D),
+        TermKeyCode::Char('o') | TermKeyCode::Char('O') => Some(KeyCode::O),
+        TermKeyCode::Char('/') => Some(KeyCode::Slash),
+        TermKeyCode::End => Some(KeyCode::End),
+        TermKeyCode::Up => Some(KeyCode::Up),
+        TermKeyCode::Down => Some(KeyCode::Down),
+        TermKeyCode::Left => Some(KeyCode::Left),
+        TermKeyCode::Right => Some(KeyCode::Right),
+        TermKeyCode::PageUp => Some(KeyCode::PageUp),
+        TermKeyCode::PageDown => Some(KeyCode::PageDown),
+        _ => None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_terminal(
+    paddle_query: Query<(&Paddle, &Transform)>,
+    ball_query: Query<&Transform, With<Ball>>,
+    score: Res<Score>,
+    lives: Res<Lives>,
+    walls: Res<Walls>,
+    settings: Res<Settings>,
+    countdown: Res<Countdown>,
+    phase: Res<State<RoundPhase>>,
+    stats: Res<MatchStats>,
+    menu_text: Res<MenuText>,
+    arena: Res<ArenaConfig>,
+) {
+    let (columns, rows) = terminal::size().unwrap_or((80, 24));
+    let width = columns.clamp(MIN_COLUMNS, MAX_COLUMNS) as usize;
+    // Leave a row for the score above the arena and one for the controls below it
+    let height = rows.saturating_sub(2).clamp(MIN_ROWS, MAX_ROWS) as usize;
+
+    let to_cell = |position: Vec3| -> Option<(usize, usize)> {
+        let column = ((position.x / arena.width + 0.5) * (width - 1) as f32).round();
+        let row = ((0.5 - position.y / arena.height) * (height - 1) as f32).round();
+
+        let inside = column >= 0.0 && column < width as f32 && row >= 0.0 && row < height as f32;
+        inside.then_some((column as usize, row as usize))
+    };
+
+    let mut grid = vec![vec![' '; width]; height];
+
+    if walls.top {
+        grid[0].fill(WALL);
+    }
+    if walls.bottom {
+        grid[height - 1].fill(WALL);
+    }
+    for line in grid.iter_mut() {
+        if walls.left {
+            line[0] = WALL;
+        }
+        if walls.right {
+            line[width - 1] = WALL;
+        }
+    }
+    for line in grid[1..height - 1].iter_mut().step_by(2) {
+        line[width / 2] = NET;
+    }
+
+    for (paddle, transform) in paddle_query.iter() {
+        if paddle.team.is_horizontal() {
+            let left = to_cell(transform.translation - Vec3::X * paddle.length / 2.0);
+            let right = to_cell(transform.translation + Vec3::X * paddle.length / 2.0);
+
+            if let (Some((left_column, row)), Some((right_column, _))) = (left, right) {
+                grid[row][left_column..=right_column.max(left_column)].fill(FLAT_PADDLE);
+            }
+            continue;
+        }
+
+        let top = to_cell(transform.translation + Vec3::Y * paddle.length / 2.0);
+        let bottom = to_cell(transform.translation - Vec3::Y * paddle.length / 2.0);
+
+        if let (Some((column, top_row)), Some((_, bottom_row))) = (top, bottom) {
+            let (top_row, bottom_row) = (top_row.max(1), bottom_row.min(height - 2));
+
+            for line in grid[top_row..=bottom_row.max(top_row)].iter_mut() {
+                line[column] = PADDLE;
+            }
+        }
+    }
+
+    for transform in ball_query.iter() {
+        if let Some((column, row)) = to_cell(transform.translation) {
+            grid[row][column] = BALL;
+        }
+    }
+
+    // The countdown is written over the net in the middle of the arena
+    let label = countdown.label();
+    let start = (width / 2).saturating_sub(label.len() / 2);
+    for (column, character) in label.chars().enumerate() {
+        if let Some(cell) = grid[height / 2].get_mut(start + column) {
+            *cell = character;
+        }
+    }
+
+    if *phase.get() == RoundPhase::MatchOver {
+        draw_overlay(&mut grid, &stats.summary());
+    }
+    if !menu_text.text.is_empty() {
+        draw_overlay(&mut grid, &menu_text.text);
+    }
+
+    // Special meters follow the score, in player order
+    let mut meters: Vec<(u32, String)> = paddle_query
+        .iter()
+        .map(|(paddle, _)| {
+            let player = paddle.player_type.number();
+            (player, format!("P{player} {}", meter_label(paddle)))
+        })
+        .collect();
+    meters.sort();
+    let meters: Vec<String> = meters.into_iter().map(|(_, meter)| meter).collect();
+
+    let (scoreline, controls) = if settings.mode.is_four_player() {
+        let counters = [
+            (PlayerType::Player1, "P1"),
+            (PlayerType::Player2, "P2"),
+            (PlayerType::Player3, "P3"),
+            (PlayerType::Player4, "P4"),
+        ]
+        .map(|(player, label)| format!("{label} {}", lives.label(player)));
+
+        (
+            counters.join("  "),
+            "W/S, Up/Down, J/L and Left/Right to move, D, /, O and End for specials, Q to quit",
+        )
+    } else {
+        (
+            format!(
+                "P1 {:02} - {:02} P2",
+                score.points(PlayerType::Player1),
+                score.points(PlayerType::Player2)
+            ),
+            match settings.mode {
+                GameMode::Doubles => {
+                    "W/S, Up/Down, I/K and PgUp/PgDn to move, D, /, O and End for specials"
+                }
+                _ => "W/S and Up/Down to move, D and / for specials, Space to serve, Q to quit",
+            },
+        )
+    };
+
+    let mut stdout = io::stdout();
+    let _ = queue!(
+        stdout,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(ClearType::CurrentLine),
+        Print(
+            format!("{:^width$}", format!("{scoreline}   {}", meters.join(" ")))
+                .chars()
+                .take(width)
+                .collect::<String>()
+        )
+    );
+    for (row, line) in grid.iter().enumerate() {
+        let line: String = line.iter().collect();
+        let _ = queue!(stdout, cursor::MoveTo(0, row as u16 + 1), Print(line));
+    }
+    let _ = queue!(
+        stdout,
+        cursor::MoveTo(0, height as u16 + 1),
+        terminal::Clear(ClearType::CurrentLine),
+        Print(&controls[..controls.len().min(width)])
+    );
+    let _ = stdout.flush();
+}
+
+// Writes the menu or post-match summary over the middle of the arena, clearing the lines
+// behind it
+fn draw_overlay(grid: &mut [Vec<char>], summary: &str) {
+    let lines: Vec<&str> = summary.lines().collect();
+    let width = grid[0].len();
+    let top = (grid.len() / 2).saturating_sub(lines.len() / 2).max(1);
+    let bottom = grid.len() - 1;
+
+    for (line, row) in lines.iter().zip(grid[top..bottom].iter_mut()) {
+        let line: String = line.chars().take(width.saturating_sub(2)).collect();
+        let start = (width - line.chars().count()) / 2;
+
+        row[1..width - 1].fill(' ');
+        for (column, character) in line.chars().enumerate() {
+            row[start + column] = character;
+        }
+    }
+}